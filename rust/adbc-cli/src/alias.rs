@@ -0,0 +1,152 @@
+//! Renaming result columns for display and export, e.g. to give a view's
+//! cryptic column names friendly labels in a CSV header.
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use arrow_array::{RecordBatch, RecordBatchReader};
+use arrow_schema::{ArrowError, Field, Schema, SchemaRef};
+
+/// An `old=new` column rename.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Alias {
+    from: String,
+    to: String,
+}
+
+impl FromStr for Alias {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        match spec.split_once('=') {
+            Some((from, to)) if !from.is_empty() && !to.is_empty() => Ok(Alias {
+                from: from.to_string(),
+                to: to.to_string(),
+            }),
+            _ => Err(format!("expected OLD=NEW, got '{}'", spec)),
+        }
+    }
+}
+
+impl Alias {
+    /// Returns true if this alias renames `name`, matching case-insensitively
+    /// if `schema` has no exact match since Snowflake upper-cases unquoted
+    /// names.
+    fn matches(&self, name: &str, schema: &Schema) -> bool {
+        name == self.from
+            || (schema.index_of(&self.from).is_err() && name.eq_ignore_ascii_case(&self.from))
+    }
+}
+
+/// `schema` with its fields renamed by `aliases`.
+fn renamed_schema(schema: &Schema, aliases: &[Alias]) -> SchemaRef {
+    let fields: Vec<Field> = schema
+        .fields()
+        .iter()
+        .map(|field| {
+            match aliases
+                .iter()
+                .find(|alias| alias.matches(field.name(), schema))
+            {
+                Some(alias) => field.as_ref().clone().with_name(&alias.to),
+                None => field.as_ref().clone(),
+            }
+        })
+        .collect();
+    Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()))
+}
+
+/// Wraps a reader, renaming the columns of every batch.
+pub struct Renamed<R> {
+    inner: R,
+    aliases: Vec<Alias>,
+    schema: SchemaRef,
+}
+
+impl<R: RecordBatchReader> Renamed<R> {
+    /// Fails, naming every unknown column, if an alias refers to a column the
+    /// result doesn't have.
+    pub fn new(inner: R, aliases: &[Alias]) -> Result<Self, ArrowError> {
+        let schema = inner.schema();
+        let unknown: Vec<&str> = aliases
+            .iter()
+            .filter(|alias| {
+                !schema
+                    .fields()
+                    .iter()
+                    .any(|field| alias.matches(field.name(), &schema))
+            })
+            .map(|alias| alias.from.as_str())
+            .collect();
+        if !unknown.is_empty() {
+            return Err(ArrowError::SchemaError(format!(
+                "--alias: no column named {}",
+                unknown.join(", ")
+            )));
+        }
+        Ok(Self {
+            schema: renamed_schema(&schema, aliases),
+            inner,
+            aliases: aliases.to_vec(),
+        })
+    }
+}
+
+impl<R: RecordBatchReader> Iterator for Renamed<R> {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let batch = self.inner.next()?;
+        Some(batch.and_then(|batch| {
+            let schema = renamed_schema(&batch.schema(), &self.aliases);
+            RecordBatch::try_new(schema, batch.columns().to_vec())
+        }))
+    }
+}
+
+impl<R: RecordBatchReader> RecordBatchReader for Renamed<R> {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{Int64Array, RecordBatchIterator};
+    use arrow_schema::DataType;
+
+    use super::*;
+
+    #[test]
+    fn rename_columns() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("C_1", DataType::Int64, true),
+            Field::new("C_2", DataType::Int64, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![1])),
+                Arc::new(Int64Array::from(vec![2])),
+            ],
+        )
+        .unwrap();
+        let reader = |aliases: &[&str]| {
+            let aliases: Vec<Alias> = aliases.iter().map(|a| a.parse().unwrap()).collect();
+            let batches = RecordBatchIterator::new(vec![Ok(batch.clone())], schema.clone());
+            Renamed::new(batches, &aliases)
+        };
+
+        let renamed = reader(&["c_1=Customer ID"]).unwrap();
+        let names = |schema: &Schema| -> Vec<String> {
+            schema.fields().iter().map(|f| f.name().clone()).collect()
+        };
+        assert_eq!(names(&renamed.schema()), ["Customer ID", "C_2"]);
+        let batches: Vec<RecordBatch> = renamed.map(Result::unwrap).collect();
+        assert_eq!(names(&batches[0].schema()), ["Customer ID", "C_2"]);
+
+        let err = reader(&["C_1=a", "X=b", "Y=c"]).err().unwrap();
+        assert!(err.to_string().contains("no column named X, Y"));
+        assert!("C_1".parse::<Alias>().is_err());
+    }
+}
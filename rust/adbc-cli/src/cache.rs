@@ -0,0 +1,253 @@
+//! An in-memory cache of query results for `--cache`, so re-running the same
+//! query (e.g. after toggling `\describe`) replays the fetched batches instead
+//! of querying Snowflake again.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use arrow_array::{RecordBatch, RecordBatchReader};
+use arrow_schema::{ArrowError, SchemaRef};
+
+use crate::size::ByteSize;
+use crate::sql;
+
+/// Statements whose results are cached. Other read-only statements (such as
+/// `USE`) return nothing worth keeping.
+const CACHEABLE_KEYWORDS: &[&str] = &["SELECT", "WITH", "SHOW", "DESCRIBE", "DESC", "VALUES"];
+
+/// A cache shared by every query of a session.
+pub type SharedCache = Arc<Mutex<ResultCache>>;
+
+#[derive(Debug)]
+struct Entry {
+    key: String,
+    schema: SchemaRef,
+    batches: Vec<RecordBatch>,
+    rows: usize,
+    bytes: u64,
+}
+
+/// Results of recent queries, keyed by their normalized text, holding at most
+/// `max_rows` rows and `max_bytes` bytes; the least recently used results are
+/// evicted first.
+#[derive(Debug)]
+pub struct ResultCache {
+    entries: VecDeque<Entry>,
+    max_rows: usize,
+    max_bytes: u64,
+}
+
+impl ResultCache {
+    pub fn new(max_rows: usize, max_bytes: ByteSize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            max_rows,
+            max_bytes: max_bytes.0,
+        }
+    }
+
+    pub fn shared(max_rows: usize, max_bytes: ByteSize) -> SharedCache {
+        Arc::new(Mutex::new(Self::new(max_rows, max_bytes)))
+    }
+
+    /// The cached result of `query`, if any.
+    pub fn get(&mut self, query: &str) -> Option<(SchemaRef, Vec<RecordBatch>)> {
+        let key = key(query);
+        let idx = self.entries.iter().position(|entry| entry.key == key)?;
+        let entry = self.entries.remove(idx)?;
+        let result = (entry.schema.clone(), entry.batches.clone());
+        self.entries.push_back(entry);
+        Some(result)
+    }
+
+    fn insert(&mut self, entry: Entry) {
+        self.entries.retain(|existing| existing.key != entry.key);
+        self.entries.push_back(entry);
+        while self.rows() > self.max_rows || self.bytes() > self.max_bytes {
+            self.entries.pop_front();
+        }
+    }
+
+    /// Drop every cached result, returning how many there were.
+    pub fn clear(&mut self) -> usize {
+        let cleared = self.entries.len();
+        self.entries.clear();
+        cleared
+    }
+
+    fn rows(&self) -> usize {
+        self.entries.iter().map(|entry| entry.rows).sum()
+    }
+
+    fn bytes(&self) -> u64 {
+        self.entries.iter().map(|entry| entry.bytes).sum()
+    }
+}
+
+/// Returns true if the result of `query` may be cached.
+pub fn is_cacheable(query: &str) -> bool {
+    sql::first_keyword(query).is_some_and(|keyword| CACHEABLE_KEYWORDS.contains(&keyword.as_str()))
+}
+
+/// Returns true if `query` invalidates cached results: anything that could
+/// modify data, and `USE`, `SET`, `UNSET`, and `ALTER SESSION`, after which
+/// the same query may read a different database, schema, or role's view.
+pub fn invalidates(query: &str) -> bool {
+    !sql::is_read_only(query)
+        || sql::first_keyword(query)
+            .is_some_and(|keyword| matches!(keyword.as_str(), "USE" | "SET" | "UNSET"))
+}
+
+/// The cache key of `query`: its text with runs of whitespace outside string
+/// literals collapsed and any trailing `;` removed, so reformatting a query
+/// doesn't miss the cache.
+fn key(query: &str) -> String {
+    let mut key = String::with_capacity(query.len());
+    let mut in_string = false;
+    let mut pending_space = false;
+    for c in query.trim().trim_end_matches(';').trim_end().chars() {
+        if !in_string && c.is_whitespace() {
+            pending_space = true;
+            continue;
+        }
+        if pending_space {
+            key.push(' ');
+            pending_space = false;
+        }
+        if c == '\'' {
+            in_string = !in_string;
+        }
+        key.push(c);
+    }
+    key
+}
+
+/// Wraps a reader, keeping a copy of its batches and caching them once it's
+/// drained. A result that errors or outgrows the cache isn't cached.
+pub struct Recorder<R> {
+    inner: R,
+    cache: SharedCache,
+    key: String,
+    batches: Option<Vec<RecordBatch>>,
+    rows: usize,
+    bytes: u64,
+}
+
+impl<R: RecordBatchReader> Recorder<R> {
+    pub fn new(inner: R, cache: SharedCache, query: &str) -> Self {
+        Self {
+            inner,
+            cache,
+            key: key(query),
+            batches: Some(Vec::new()),
+            rows: 0,
+            bytes: 0,
+        }
+    }
+
+    fn record(&mut self, batch: &RecordBatch) {
+        let Some(batches) = &mut self.batches else {
+            return;
+        };
+        self.rows += batch.num_rows();
+        self.bytes += batch.get_array_memory_size() as u64;
+        let cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        if self.rows > cache.max_rows || self.bytes > cache.max_bytes {
+            self.batches = None;
+        } else {
+            batches.push(batch.clone());
+        }
+    }
+}
+
+impl<R: RecordBatchReader> Iterator for Recorder<R> {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.inner.next() {
+            Some(Ok(batch)) => {
+                self.record(&batch);
+                Some(Ok(batch))
+            }
+            Some(Err(e)) => {
+                self.batches = None;
+                Some(Err(e))
+            }
+            None => {
+                if let Some(batches) = self.batches.take() {
+                    self.cache
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .insert(Entry {
+                            key: std::mem::take(&mut self.key),
+                            schema: self.inner.schema(),
+                            batches,
+                            rows: self.rows,
+                            bytes: self.bytes,
+                        });
+                }
+                None
+            }
+        }
+    }
+}
+
+impl<R: RecordBatchReader> RecordBatchReader for Recorder<R> {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{Int64Array, RecordBatchIterator};
+    use arrow_schema::{DataType, Field, Schema};
+
+    use super::*;
+
+    fn record(cache: &SharedCache, query: &str, rows: i64) {
+        let schema = Arc::new(Schema::new(vec![Field::new("ID", DataType::Int64, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from_iter_values(0..rows))],
+        )
+        .unwrap();
+        let reader = RecordBatchIterator::new([Ok(batch)], schema);
+        assert_eq!(Recorder::new(reader, cache.clone(), query).count(), 1);
+    }
+
+    #[test]
+    fn cache_results() {
+        assert_eq!(
+            key("  SELECT  *\n  FROM t WHERE s = 'a  b' ;"),
+            "SELECT * FROM t WHERE s = 'a  b'"
+        );
+        assert!(is_cacheable("select 1"));
+        assert!(!is_cacheable("USE DATABASE d"));
+        assert!(invalidates("use database other"));
+        assert!(invalidates("ALTER SESSION SET TIMEZONE = 'UTC'"));
+        assert!(invalidates("SET x = 1"));
+        assert!(invalidates("INSERT INTO t VALUES (1)"));
+        assert!(!invalidates("SELECT * FROM t"));
+        assert!(!invalidates("SHOW TABLES"));
+
+        let cache = ResultCache::shared(15, ByteSize(1 << 20));
+        record(&cache, "SELECT * FROM a", 10);
+        // Keys are case-sensitive, since string literals are.
+        assert!(cache.lock().unwrap().get("select * from a").is_none());
+        let (_, batches) = cache.lock().unwrap().get("SELECT *  FROM a;").unwrap();
+        assert_eq!(batches[0].num_rows(), 10);
+
+        // Too large to cache at all.
+        record(&cache, "SELECT * FROM big", 20);
+        assert!(cache.lock().unwrap().get("SELECT * FROM big").is_none());
+
+        // Evicts the least recently used result to stay under 15 rows.
+        record(&cache, "SELECT * FROM b", 5);
+        record(&cache, "SELECT * FROM c", 5);
+        let mut cache = cache.lock().unwrap();
+        assert!(cache.get("SELECT * FROM a").is_none());
+        assert!(cache.get("SELECT * FROM b").is_some());
+        assert_eq!(cache.clear(), 2);
+    }
+}
@@ -0,0 +1,100 @@
+//! Canned metadata queries for the `tables`, `columns`, `warehouses`, and
+//! `users` subcommands, so occasional users needn't remember Snowflake's
+//! `INFORMATION_SCHEMA` views and `SHOW` commands.
+//!
+//! Name filters are `ILIKE` patterns: case-insensitive, with `%` and `_` as
+//! wildcards, so `--schema public` matches the `PUBLIC` schema.
+
+use anyhow::{bail, Result};
+
+use crate::sql;
+
+/// The `INFORMATION_SCHEMA` view `view`, of `database` or else the current
+/// database.
+fn view(database: Option<&str>, view: &str) -> Result<String> {
+    match database {
+        Some(database) if !sql::is_identifier(database) => {
+            bail!("Invalid database name: {}", database)
+        }
+        Some(database) => Ok(format!("{}.INFORMATION_SCHEMA.{}", database, view)),
+        None => Ok(format!("INFORMATION_SCHEMA.{}", view)),
+    }
+}
+
+/// `WHERE` conditions matching each `(column, pattern)` with a pattern.
+fn conditions(filters: &[(&str, Option<&str>)]) -> String {
+    let mut conditions = vec!["table_schema <> 'INFORMATION_SCHEMA'".to_string()];
+    for (column, pattern) in filters {
+        if let Some(pattern) = pattern {
+            conditions.push(format!("{} ILIKE {}", column, sql::string_literal(pattern)));
+        }
+    }
+    conditions.join(" AND ")
+}
+
+/// SQL listing the tables and views of `database`, optionally only those in
+/// schemas matching `schema` and with names matching `like`.
+pub fn tables_sql(
+    database: Option<&str>,
+    schema: Option<&str>,
+    like: Option<&str>,
+) -> Result<String> {
+    Ok(format!(
+        "SELECT table_catalog, table_schema, table_name, table_type, row_count, bytes, \
+         created, comment FROM {} WHERE {} ORDER BY table_schema, table_name",
+        view(database, "TABLES")?,
+        conditions(&[("table_schema", schema), ("table_name", like)])
+    ))
+}
+
+/// SQL listing the columns of the tables of `database`, in column order,
+/// optionally only for tables matching `table` in schemas matching `schema`.
+pub fn columns_sql(
+    database: Option<&str>,
+    schema: Option<&str>,
+    table: Option<&str>,
+) -> Result<String> {
+    Ok(format!(
+        "SELECT table_schema, table_name, column_name, data_type, is_nullable, \
+         column_default, comment FROM {} WHERE {} \
+         ORDER BY table_schema, table_name, ordinal_position",
+        view(database, "COLUMNS")?,
+        conditions(&[("table_schema", schema), ("table_name", table)])
+    ))
+}
+
+/// A `SHOW <objects>` command, optionally only for names matching `like`.
+pub fn show_sql(objects: &str, like: Option<&str>) -> String {
+    match like {
+        Some(pattern) => format!("SHOW {} LIKE {}", objects, sql::string_literal(pattern)),
+        None => format!("SHOW {}", objects),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata_queries() {
+        assert_eq!(
+            tables_sql(None, Some("public"), None).unwrap(),
+            "SELECT table_catalog, table_schema, table_name, table_type, row_count, bytes, \
+             created, comment FROM INFORMATION_SCHEMA.TABLES \
+             WHERE table_schema <> 'INFORMATION_SCHEMA' AND table_schema ILIKE 'public' \
+             ORDER BY table_schema, table_name"
+        );
+        assert!(columns_sql(Some("ANALYTICS"), None, Some("orders"))
+            .unwrap()
+            .contains(
+                "FROM ANALYTICS.INFORMATION_SCHEMA.COLUMNS WHERE table_schema <> \
+                       'INFORMATION_SCHEMA' AND table_name ILIKE 'orders' ORDER BY"
+            ));
+        assert!(tables_sql(Some("db; DROP TABLE t"), None, None).is_err());
+        assert_eq!(
+            show_sql("WAREHOUSES", Some("it's%")),
+            "SHOW WAREHOUSES LIKE 'it''s%'"
+        );
+        assert_eq!(show_sql("USERS", None), "SHOW USERS");
+    }
+}
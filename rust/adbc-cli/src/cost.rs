@@ -0,0 +1,85 @@
+//! Estimating how much data a query will scan before running it, for
+//! `--confirm-cost`.
+//!
+//! Snowflake's `EXPLAIN USING JSON` compiles a query without running it and
+//! reports the partitions and bytes it expects to scan after pruning. Credits
+//! are billed by warehouse time rather than bytes, but the scan size is the
+//! best cheap proxy for how long a query will keep a warehouse busy.
+
+use std::fmt;
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+use crate::size::ByteSize;
+use crate::sql;
+
+/// Statements `EXPLAIN` can compile.
+const EXPLAINABLE_KEYWORDS: &[&str] = &["SELECT", "WITH", "INSERT", "UPDATE", "DELETE", "MERGE"];
+
+/// The scan Snowflake expects for a query, from its compiled plan.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Estimate {
+    pub bytes: ByteSize,
+    pub partitions_assigned: u64,
+    pub partitions_total: u64,
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "~{} scan ({} of {} partitions)",
+            self.bytes, self.partitions_assigned, self.partitions_total
+        )
+    }
+}
+
+/// The `EXPLAIN` statement estimating `query`, or `None` if it isn't a
+/// statement `EXPLAIN` accepts.
+pub fn explain_sql(query: &str) -> Option<String> {
+    let keyword = sql::first_keyword(query)?;
+    EXPLAINABLE_KEYWORDS
+        .contains(&keyword.as_str())
+        .then(|| format!("EXPLAIN USING JSON {}", query.trim().trim_end_matches(';')))
+}
+
+/// Parse the `GlobalStats` of an `EXPLAIN USING JSON` plan.
+pub fn parse_plan(plan: &str) -> Result<Estimate> {
+    let plan: Value = serde_json::from_str(plan).context("EXPLAIN returned invalid JSON")?;
+    let stats = plan
+        .get("GlobalStats")
+        .context("EXPLAIN plan has no GlobalStats")?;
+    let stat = |name: &str| {
+        stats
+            .get(name)
+            .and_then(Value::as_u64)
+            .with_context(|| format!("EXPLAIN plan has no {}", name))
+    };
+    Ok(Estimate {
+        bytes: ByteSize(stat("bytesAssigned")?),
+        partitions_assigned: stat("partitionsAssigned")?,
+        partitions_total: stat("partitionsTotal")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimate_from_plan() {
+        assert_eq!(
+            explain_sql("select * from t;").as_deref(),
+            Some("EXPLAIN USING JSON select * from t")
+        );
+        assert_eq!(explain_sql("SHOW TABLES"), None);
+
+        let plan = r#"{"GlobalStats": {"partitionsTotal": 500, "partitionsAssigned": 120,
+            "bytesAssigned": 2254857830}, "Operations": [[]]}"#;
+        let estimate = parse_plan(plan).unwrap();
+        assert_eq!(estimate.bytes, ByteSize(2_254_857_830));
+        assert_eq!(estimate.to_string(), "~2.1 GB scan (120 of 500 partitions)");
+        assert!(parse_plan("{}").is_err());
+    }
+}
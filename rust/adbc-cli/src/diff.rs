@@ -0,0 +1,486 @@
+//! Comparing the results of one query run against two profiles, row by row
+//! or, with `--key`, by the value of a key column.
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use anyhow::{bail, Result};
+use arrow_array::{Array, RecordBatch};
+use arrow_cast::display::{ArrayFormatter, FormatOptions};
+use arrow_schema::Schema;
+
+/// A row that differs between the two results. A missing side means the
+/// other result has more rows or, matching by key, that the key was added or
+/// removed.
+#[derive(Debug, PartialEq, Eq)]
+pub struct RowDiff {
+    /// The row's position in the result, or its position on the left when
+    /// matching by key.
+    pub row: usize,
+    /// The row's key value, when matching by key.
+    pub key: Option<String>,
+    pub left: Option<Vec<String>>,
+    pub right: Option<Vec<String>>,
+}
+
+/// The outcome of comparing two results.
+#[derive(Debug, Default)]
+pub struct Diff {
+    /// Column-by-column description of how the schemas differ; rows are not
+    /// compared when this is non-empty.
+    pub schema_differences: Vec<String>,
+    pub left_rows: usize,
+    pub right_rows: usize,
+    /// The first differing rows, up to the requested limit.
+    pub rows: Vec<RowDiff>,
+    pub total_row_differences: usize,
+    /// The key column rows were matched by, with the keys only on the right,
+    /// only on the left, and on both sides with different rows.
+    pub key: Option<String>,
+    pub added: usize,
+    pub removed: usize,
+    pub changed: usize,
+}
+
+impl Diff {
+    pub fn is_match(&self) -> bool {
+        self.schema_differences.is_empty() && self.total_row_differences == 0
+    }
+
+    fn push(&mut self, row: RowDiff, max_rows: usize) {
+        self.total_row_differences += 1;
+        if self.rows.len() < max_rows {
+            self.rows.push(row);
+        }
+    }
+}
+
+/// A row's values, each rendered losslessly, with `None` for NULL.
+type Row = Vec<Option<String>>;
+
+/// Render every row of `batches` with Arrow's formatter, which writes every
+/// type in full (binary as hex, decimals at their scale, nested values
+/// element by element), so rows compare equal only if their values are.
+fn rows(batches: &[RecordBatch]) -> Result<Vec<Row>> {
+    let options = FormatOptions::default();
+    let mut rows = Vec::new();
+    for batch in batches {
+        let formatters = batch
+            .columns()
+            .iter()
+            .map(|col| ArrayFormatter::try_new(col.as_ref(), &options))
+            .collect::<Result<Vec<_>, _>>()?;
+        for row_idx in 0..batch.num_rows() {
+            rows.push(
+                formatters
+                    .iter()
+                    .zip(batch.columns())
+                    .map(|(formatter, col)| {
+                        col.is_valid(row_idx)
+                            .then(|| formatter.value(row_idx).to_string())
+                    })
+                    .collect(),
+            );
+        }
+    }
+    Ok(rows)
+}
+
+/// `row` as shown in a report.
+fn display(row: &Row) -> Vec<String> {
+    row.iter()
+        .map(|value| value.clone().unwrap_or_else(|| "NULL".to_string()))
+        .collect()
+}
+
+fn schema_differences(left: &Schema, right: &Schema) -> Vec<String> {
+    let mut differences = Vec::new();
+    if left.fields().len() != right.fields().len() {
+        differences.push(format!(
+            "column count: {} vs {}",
+            left.fields().len(),
+            right.fields().len()
+        ));
+    }
+    for (idx, (l, r)) in left.fields().iter().zip(right.fields()).enumerate() {
+        if l.name() != r.name() || l.data_type() != r.data_type() {
+            differences.push(format!(
+                "column {}: {} {} vs {} {}",
+                idx + 1,
+                l.name(),
+                l.data_type(),
+                r.name(),
+                r.data_type()
+            ));
+        }
+    }
+    differences
+}
+
+/// Compare two results row by row. With `sort`, rows are sorted first so
+/// results that differ only in order match. At most `max_rows` differing
+/// rows are kept, though all are counted.
+pub fn compare(
+    left: (&Schema, &[RecordBatch]),
+    right: (&Schema, &[RecordBatch]),
+    sort: bool,
+    max_rows: usize,
+) -> Result<Diff> {
+    let mut left_rows = rows(left.1)?;
+    let mut right_rows = rows(right.1)?;
+    let mut diff = Diff {
+        schema_differences: schema_differences(left.0, right.0),
+        left_rows: left_rows.len(),
+        right_rows: right_rows.len(),
+        ..Default::default()
+    };
+    if !diff.schema_differences.is_empty() {
+        return Ok(diff);
+    }
+
+    if sort {
+        left_rows.sort();
+        right_rows.sort();
+    }
+
+    for row in 0..left_rows.len().max(right_rows.len()) {
+        let (l, r) = (left_rows.get(row), right_rows.get(row));
+        if l != r {
+            let row = RowDiff {
+                row: row + 1,
+                key: None,
+                left: l.map(display),
+                right: r.map(display),
+            };
+            diff.push(row, max_rows);
+        }
+    }
+    Ok(diff)
+}
+
+/// Index `rows` by their value in column `key`, failing on a duplicate key,
+/// which would make the match ambiguous. NULL keys, which match nothing, are
+/// left out.
+fn index_by_key<'a>(rows: &'a [Row], key: usize, side: &str) -> Result<HashMap<&'a str, &'a Row>> {
+    let mut index = HashMap::with_capacity(rows.len());
+    for row in rows {
+        let Some(value) = &row[key] else { continue };
+        if index.insert(value.as_str(), row).is_some() {
+            bail!("Key '{}' appears more than once in {}", value, side);
+        }
+    }
+    Ok(index)
+}
+
+/// Count the rows of `rows` with a NULL in column `key`.
+fn null_keys(rows: &[Row], key: usize) -> HashMap<&Row, usize> {
+    let mut counts = HashMap::new();
+    for row in rows.iter().filter(|row| row[key].is_none()) {
+        *counts.entry(row).or_default() += 1;
+    }
+    counts
+}
+
+/// Take one of `row` from `counts`, returning whether there was one.
+fn take_one(counts: &mut HashMap<&Row, usize>, row: &Row) -> bool {
+    match counts.get_mut(row) {
+        Some(count) if *count > 0 => {
+            *count -= 1;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Compare two results by matching rows on the value of column `key` rather
+/// than their position, so results in different orders compare correctly.
+/// Removed and changed keys are reported in the left result's order, then
+/// added keys in the right's. Rows with a NULL key can't be matched by it, so
+/// each matches an identical row on the other side or is removed or added.
+/// At most `max_rows` differing rows are kept, though all are counted.
+pub fn compare_by_key(
+    left: (&Schema, &[RecordBatch]),
+    right: (&Schema, &[RecordBatch]),
+    key: &str,
+    max_rows: usize,
+) -> Result<Diff> {
+    let left_rows = rows(left.1)?;
+    let right_rows = rows(right.1)?;
+    let mut diff = Diff {
+        schema_differences: schema_differences(left.0, right.0),
+        left_rows: left_rows.len(),
+        right_rows: right_rows.len(),
+        ..Default::default()
+    };
+    if !diff.schema_differences.is_empty() {
+        return Ok(diff);
+    }
+
+    let fields = left.0.fields();
+    let column = fields
+        .iter()
+        .position(|field| field.name() == key)
+        .or_else(|| {
+            fields
+                .iter()
+                .position(|field| field.name().eq_ignore_ascii_case(key))
+        });
+    let Some(column) = column else {
+        bail!("Key column '{}' is not in the results", key);
+    };
+    diff.key = Some(fields[column].name().clone());
+    let left_index = index_by_key(&left_rows, column, "the left results")?;
+    let right_index = index_by_key(&right_rows, column, "the right results")?;
+
+    let mut left_nulls = null_keys(&left_rows, column);
+    let mut right_nulls = null_keys(&right_rows, column);
+
+    for (idx, l) in left_rows.iter().enumerate() {
+        let r = match &l[column] {
+            Some(key) => right_index.get(key.as_str()),
+            None if take_one(&mut right_nulls, l) => continue,
+            None => None,
+        };
+        if r == Some(&l) {
+            continue;
+        }
+        if r.is_some() {
+            diff.changed += 1;
+        } else {
+            diff.removed += 1;
+        }
+        let row = RowDiff {
+            row: idx + 1,
+            key: Some(display(l).swap_remove(column)),
+            left: Some(display(l)),
+            right: r.map(|r| display(r)),
+        };
+        diff.push(row, max_rows);
+    }
+    for (idx, r) in right_rows.iter().enumerate() {
+        let added = match &r[column] {
+            Some(key) => !left_index.contains_key(key.as_str()),
+            None => !take_one(&mut left_nulls, r),
+        };
+        if added {
+            diff.added += 1;
+            let row = RowDiff {
+                row: idx + 1,
+                key: Some(display(r).swap_remove(column)),
+                left: None,
+                right: Some(display(r)),
+            };
+            diff.push(row, max_rows);
+        }
+    }
+    Ok(diff)
+}
+
+/// Write a human-readable report of `diff`, labelling each side by profile.
+pub fn write_report(
+    diff: &Diff,
+    left_name: &str,
+    right_name: &str,
+    handle: &mut dyn Write,
+) -> Result<()> {
+    if !diff.schema_differences.is_empty() {
+        writeln!(handle, "Schemas differ ({} vs {}):", left_name, right_name)?;
+        for difference in &diff.schema_differences {
+            writeln!(handle, "  {}", difference)?;
+        }
+        return Ok(());
+    }
+
+    writeln!(
+        handle,
+        "Rows: {} ({}) vs {} ({})",
+        diff.left_rows, left_name, diff.right_rows, right_name
+    )?;
+    if diff.is_match() {
+        writeln!(handle, "Results match.")?;
+        return Ok(());
+    }
+
+    match &diff.key {
+        Some(key) => writeln!(
+            handle,
+            "{} keys differ by {}: {} added, {} removed, {} changed",
+            diff.total_row_differences, key, diff.added, diff.removed, diff.changed
+        )?,
+        None => writeln!(handle, "{} rows differ", diff.total_row_differences)?,
+    }
+    let show = |row: &Option<Vec<String>>| match row {
+        Some(values) => values.join(", "),
+        None => "(missing)".to_string(),
+    };
+    for row in &diff.rows {
+        match (&row.key, &row.left, &row.right) {
+            (Some(key), None, _) => writeln!(handle, "\nAdded {}:", key)?,
+            (Some(key), _, None) => writeln!(handle, "\nRemoved {}:", key)?,
+            (Some(key), _, _) => writeln!(handle, "\nChanged {}:", key)?,
+            (None, _, _) => writeln!(handle, "\nRow {}:", row.row)?,
+        }
+        writeln!(handle, "  {}: {}", left_name, show(&row.left))?;
+        writeln!(handle, "  {}: {}", right_name, show(&row.right))?;
+    }
+    if diff.total_row_differences > diff.rows.len() {
+        writeln!(
+            handle,
+            "\n... ({} more differing rows)",
+            diff.total_row_differences - diff.rows.len()
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::{BinaryArray, Decimal128Array, Int64Array, StringArray};
+    use arrow_schema::{DataType, Field};
+
+    use super::*;
+
+    fn batch(ids: Vec<i64>) -> RecordBatch {
+        let schema = Schema::new(vec![Field::new("ID", DataType::Int64, true)]);
+        RecordBatch::try_new(Arc::new(schema), vec![Arc::new(Int64Array::from(ids))]).unwrap()
+    }
+
+    #[test]
+    fn compare_rows() {
+        let left = [batch(vec![1, 2, 3])];
+        let right = [batch(vec![3, 2, 1])];
+        let schema = left[0].schema();
+
+        let diff = compare((&schema, &left), (&schema, &right), false, 10).unwrap();
+        assert_eq!(diff.total_row_differences, 2);
+        assert_eq!(diff.rows[0].row, 1);
+
+        let diff = compare((&schema, &left), (&schema, &right), true, 10).unwrap();
+        assert!(diff.is_match());
+
+        let right = [batch(vec![1, 2])];
+        let diff = compare((&schema, &left), (&schema, &right), false, 10).unwrap();
+        assert_eq!((diff.left_rows, diff.right_rows), (3, 2));
+        assert_eq!(
+            diff.rows,
+            vec![RowDiff {
+                row: 3,
+                key: None,
+                left: Some(vec!["3".to_string()]),
+                right: None
+            }]
+        );
+    }
+
+    #[test]
+    fn compare_values_of_every_type() {
+        let batch = |bytes: Vec<&[u8]>, amounts: Vec<i128>| {
+            let schema = Schema::new(vec![
+                Field::new("BYTES", DataType::Binary, true),
+                Field::new("AMOUNT", DataType::Decimal128(10, 2), true),
+            ]);
+            let amounts = Decimal128Array::from(amounts)
+                .with_precision_and_scale(10, 2)
+                .unwrap();
+            RecordBatch::try_new(
+                Arc::new(schema),
+                vec![Arc::new(BinaryArray::from(bytes)), Arc::new(amounts)],
+            )
+            .unwrap()
+        };
+        let left = [batch(vec![b"ab", b"cd"], vec![150, 200])];
+        let right = [batch(vec![b"ab", b"ce"], vec![150, 200])];
+        let schema = left[0].schema();
+        let diff = compare((&schema, &left), (&schema, &right), false, 10).unwrap();
+        assert_eq!(diff.total_row_differences, 1);
+        assert_eq!(
+            diff.rows[0].left,
+            Some(vec!["6364".to_string(), "2.00".to_string()])
+        );
+    }
+
+    fn keyed(ids: Vec<i64>, names: Vec<&str>) -> RecordBatch {
+        let schema = Schema::new(vec![
+            Field::new("ID", DataType::Int64, true),
+            Field::new("NAME", DataType::Utf8, true),
+        ]);
+        RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Int64Array::from(ids)),
+                Arc::new(StringArray::from(names)),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn compare_rows_by_key() {
+        let left = [keyed(vec![1, 2, 3], vec!["a", "b", "c"])];
+        let right = [keyed(vec![4, 3, 2], vec!["d", "c", "B"])];
+        let schema = left[0].schema();
+
+        let diff = compare_by_key((&schema, &left), (&schema, &right), "id", 10).unwrap();
+        assert_eq!(diff.key.as_deref(), Some("ID"));
+        assert_eq!((diff.added, diff.removed, diff.changed), (1, 1, 1));
+        let keys: Vec<_> = diff.rows.iter().map(|row| row.key.as_deref()).collect();
+        assert_eq!(keys, [Some("1"), Some("2"), Some("4")]);
+        assert_eq!(
+            diff.rows[1].right,
+            Some(vec!["2".to_string(), "B".to_string()])
+        );
+
+        let mut out = Vec::new();
+        write_report(&diff, "dev", "prod", &mut out).unwrap();
+        let report = String::from_utf8(out).unwrap();
+        assert!(report.contains("3 keys differ by ID: 1 added, 1 removed, 1 changed"));
+        assert!(report.contains("\nRemoved 1:\n  dev: 1, a\n  prod: (missing)\n"));
+
+        let shuffled = [keyed(vec![3, 1, 2], vec!["c", "a", "b"])];
+        let diff = compare_by_key((&schema, &left), (&schema, &shuffled), "ID", 10).unwrap();
+        assert!(diff.is_match());
+
+        assert!(compare_by_key((&schema, &left), (&schema, &right), "NOPE", 10).is_err());
+        let duplicated = [keyed(vec![1, 1], vec!["a", "b"])];
+        assert!(compare_by_key((&schema, &duplicated), (&schema, &right), "ID", 10).is_err());
+
+        // NULL keys aren't duplicates; each matches an identical row.
+        let nulls = |names: Vec<&str>| {
+            let schema = Schema::new(vec![
+                Field::new("ID", DataType::Int64, true),
+                Field::new("NAME", DataType::Utf8, true),
+            ]);
+            let ids = Int64Array::from(vec![None; names.len()]);
+            RecordBatch::try_new(
+                Arc::new(schema),
+                vec![Arc::new(ids), Arc::new(StringArray::from(names))],
+            )
+            .unwrap()
+        };
+        let left = [nulls(vec!["a", "b", "b"])];
+        let diff = compare_by_key((&schema, &left), (&schema, &left), "ID", 10).unwrap();
+        assert!(diff.is_match());
+        let right = [nulls(vec!["b", "c"])];
+        let diff = compare_by_key((&schema, &left), (&schema, &right), "ID", 10).unwrap();
+        assert_eq!((diff.added, diff.removed, diff.changed), (1, 2, 0));
+        assert_eq!(diff.rows[0].key.as_deref(), Some("NULL"));
+    }
+
+    #[test]
+    fn compare_schemas() {
+        let left = [batch(vec![1])];
+        let schema = Schema::new(vec![Field::new("ID", DataType::Utf8, true)]);
+        let right = [RecordBatch::try_new(
+            Arc::new(schema.clone()),
+            vec![Arc::new(StringArray::from(vec!["1"]))],
+        )
+        .unwrap()];
+
+        let diff = compare((&left[0].schema(), &left), (&schema, &right), false, 10).unwrap();
+        assert!(!diff.is_match());
+        assert_eq!(diff.schema_differences, ["column 1: ID Int64 vs ID Utf8"]);
+        assert!(diff.rows.is_empty());
+    }
+}
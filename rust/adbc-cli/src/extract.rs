@@ -0,0 +1,247 @@
+//! Client-side extraction of nested fields from JSON (e.g. VARIANT) columns,
+//! so `--extract 'V:$.a.b'` shows `a.b` without writing a path expression in
+//! SQL.
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use arrow_array::cast::AsArray;
+use arrow_array::{Array, ArrayRef, RecordBatch, RecordBatchReader, StringArray};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+use serde_json::Value;
+
+/// One step of a JSON path.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Step {
+    Key(String),
+    Index(usize),
+}
+
+/// A `column:$.path` extraction, displayed as a column named after the spec.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Extraction {
+    spec: String,
+    column: String,
+    path: Vec<Step>,
+}
+
+/// Parse a path such as `$.a.b[0]["c d"]` into steps.
+fn parse_path(path: &str) -> Result<Vec<Step>, String> {
+    let mut rest = path
+        .strip_prefix('$')
+        .ok_or_else(|| format!("path '{}' must start with '$'", path))?;
+    let mut steps = Vec::new();
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            if end == 0 {
+                return Err(format!("empty key in path '{}'", path));
+            }
+            steps.push(Step::Key(after[..end].to_string()));
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let end = after
+                .find(']')
+                .ok_or_else(|| format!("unclosed '[' in path '{}'", path))?;
+            let inner = &after[..end];
+            let step = match inner
+                .strip_prefix('"')
+                .and_then(|key| key.strip_suffix('"'))
+            {
+                Some(key) => Step::Key(key.to_string()),
+                None => Step::Index(
+                    inner
+                        .parse()
+                        .map_err(|_| format!("invalid index '{}' in path '{}'", inner, path))?,
+                ),
+            };
+            steps.push(step);
+            rest = &after[end + 1..];
+        } else {
+            return Err(format!("expected '.' or '[' in path '{}'", path));
+        }
+    }
+    Ok(steps)
+}
+
+impl FromStr for Extraction {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let (column, path) = spec
+            .split_once(":$")
+            .ok_or_else(|| format!("expected COLUMN:$.path, got '{}'", spec))?;
+        Ok(Extraction {
+            spec: spec.to_string(),
+            column: column.to_string(),
+            path: parse_path(&format!("${}", path))?,
+        })
+    }
+}
+
+impl Extraction {
+    /// Returns the value at this path in `json`, or `None` if the path is
+    /// missing, the value is JSON null, or `json` isn't valid JSON. Strings
+    /// are returned unquoted; other values as compact JSON.
+    fn evaluate(&self, json: &str) -> Option<String> {
+        let root: Value = serde_json::from_str(json).ok()?;
+        let mut value = &root;
+        for step in &self.path {
+            value = match step {
+                Step::Key(key) => value.get(key)?,
+                Step::Index(idx) => value.get(idx)?,
+            };
+        }
+        match value {
+            Value::Null => None,
+            Value::String(s) => Some(s.clone()),
+            other => Some(other.to_string()),
+        }
+    }
+
+    /// Returns the index of the source column in `schema`, matching
+    /// case-insensitively if there's no exact match since Snowflake
+    /// upper-cases unquoted names.
+    fn column_index(&self, schema: &Schema) -> Result<usize, ArrowError> {
+        let idx = schema.index_of(&self.column).or_else(|_| {
+            schema
+                .fields()
+                .iter()
+                .position(|f| f.name().eq_ignore_ascii_case(&self.column))
+                .ok_or_else(|| {
+                    ArrowError::SchemaError(format!("--extract: no column named {}", self.column))
+                })
+        })?;
+        match schema.field(idx).data_type() {
+            DataType::Utf8 | DataType::LargeUtf8 => Ok(idx),
+            other => Err(ArrowError::SchemaError(format!(
+                "--extract: column {} is {}, not a JSON string",
+                self.column, other
+            ))),
+        }
+    }
+}
+
+/// `schema` with a nullable string column appended for each extraction.
+fn extended_schema(schema: &Schema, extractions: &[Extraction]) -> Result<SchemaRef, ArrowError> {
+    let mut fields: Vec<Field> = schema.fields().iter().map(|f| f.as_ref().clone()).collect();
+    for extraction in extractions {
+        extraction.column_index(schema)?;
+        fields.push(Field::new(&extraction.spec, DataType::Utf8, true));
+    }
+    Ok(Arc::new(Schema::new_with_metadata(
+        fields,
+        schema.metadata().clone(),
+    )))
+}
+
+/// Append a column to `batch` for each extraction.
+fn apply(batch: RecordBatch, extractions: &[Extraction]) -> Result<RecordBatch, ArrowError> {
+    if extractions.is_empty() {
+        return Ok(batch);
+    }
+    let schema = extended_schema(&batch.schema(), extractions)?;
+    let mut columns = batch.columns().to_vec();
+    for extraction in extractions {
+        let source = batch.column(extraction.column_index(&batch.schema())?);
+        let value = |idx: usize| -> Option<String> {
+            if source.is_null(idx) {
+                return None;
+            }
+            match source.data_type() {
+                DataType::LargeUtf8 => extraction.evaluate(source.as_string::<i64>().value(idx)),
+                _ => extraction.evaluate(source.as_string::<i32>().value(idx)),
+            }
+        };
+        let extracted: StringArray = (0..batch.num_rows()).map(value).collect();
+        columns.push(Arc::new(extracted) as ArrayRef);
+    }
+    RecordBatch::try_new(schema, columns)
+}
+
+/// Wraps a reader, adding the extracted columns to every batch.
+pub struct Extract<R> {
+    inner: R,
+    extractions: Vec<Extraction>,
+    schema: SchemaRef,
+}
+
+impl<R: RecordBatchReader> Extract<R> {
+    pub fn new(inner: R, extractions: &[Extraction]) -> Result<Self, ArrowError> {
+        let schema = if extractions.is_empty() {
+            inner.schema()
+        } else {
+            extended_schema(&inner.schema(), extractions)?
+        };
+        Ok(Self {
+            inner,
+            extractions: extractions.to_vec(),
+            schema,
+        })
+    }
+}
+
+impl<R: RecordBatchReader> Iterator for Extract<R> {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let batch = self.inner.next()?;
+        Some(batch.and_then(|batch| apply(batch, &self.extractions)))
+    }
+}
+
+impl<R: RecordBatchReader> RecordBatchReader for Extract<R> {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_extractions() {
+        let extraction: Extraction = "V:$.a[1][\"b c\"]".parse().unwrap();
+        assert_eq!(extraction.column, "V");
+        assert_eq!(
+            extraction.path,
+            [
+                Step::Key("a".to_string()),
+                Step::Index(1),
+                Step::Key("b c".to_string())
+            ]
+        );
+        assert!("V.a".parse::<Extraction>().is_err());
+        assert!("V:$.a[x]".parse::<Extraction>().is_err());
+        assert!("V:$..a".parse::<Extraction>().is_err());
+    }
+
+    #[test]
+    fn extract_columns() {
+        let schema = Arc::new(Schema::new(vec![Field::new("V", DataType::Utf8, true)]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![Arc::new(StringArray::from(vec![
+                Some(r#"{"user": {"name": "ann", "tags": [1, 2]}}"#),
+                Some(r#"{"user": {}}"#),
+                None,
+            ]))],
+        )
+        .unwrap();
+        let extractions: Vec<Extraction> = ["v:$.user.name", "V:$.user.tags"]
+            .iter()
+            .map(|spec| spec.parse().unwrap())
+            .collect();
+
+        let batch = apply(batch, &extractions).unwrap();
+        assert_eq!(batch.schema().field(1).name(), "v:$.user.name");
+        let names = batch.column(1).as_string::<i32>();
+        assert_eq!(names.value(0), "ann");
+        assert!(names.is_null(1) && names.is_null(2));
+        assert_eq!(batch.column(2).as_string::<i32>().value(0), "[1,2]");
+
+        let missing: Extraction = "X:$.a".parse().unwrap();
+        assert!(apply(batch, &[missing]).is_err());
+    }
+}
@@ -0,0 +1,116 @@
+//! Reading queries from a named pipe, for `--connect-only --fifo`, so a shell
+//! script can send many queries to one persistent connection instead of
+//! logging in for each.
+//!
+//! Each writer's output is read up to its end (e.g. one `echo ... > fifo`) and
+//! handled as a script; a `\quit` line ends the script and stops reading.
+//! Writers that follow each other closely may be read as one script.
+
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+#[cfg(unix)]
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+
+/// The line that stops [`serve`].
+pub const QUIT: &str = "\\quit";
+
+/// Create a FIFO at `path` unless one exists, returning whether it was
+/// created. Any other kind of file there is an error.
+fn ensure_fifo(path: &Path) -> Result<bool> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+
+        match fs::metadata(path) {
+            Ok(metadata) if metadata.file_type().is_fifo() => return Ok(false),
+            Ok(_) => bail!("{} exists and is not a FIFO", path.display()),
+            Err(_) => {}
+        }
+        let status = Command::new("mkfifo")
+            .arg(path)
+            .status()
+            .context("Failed to run mkfifo")?;
+        if !status.success() {
+            bail!("Failed to create FIFO {} ({})", path.display(), status);
+        }
+        Ok(true)
+    }
+    #[cfg(not(unix))]
+    bail!("--fifo is only supported on Unix ({})", path.display())
+}
+
+/// Read scripts from the FIFO at `path`, creating it if needed, and pass each
+/// to `run` until a `\quit` line. A FIFO this created is removed afterwards.
+pub fn serve(path: &Path, mut run: impl FnMut(&str)) -> Result<()> {
+    let created = ensure_fifo(path)?;
+    let result = (|| -> Result<()> {
+        loop {
+            // Opening blocks until a writer opens the other end, and reading
+            // ends when it closes it.
+            let mut script = String::new();
+            File::open(path)
+                .and_then(|mut fifo| fifo.read_to_string(&mut script))
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let mut lines = Vec::new();
+            let mut quit = false;
+            for line in script.lines() {
+                if line.trim() == QUIT {
+                    quit = true;
+                    break;
+                }
+                lines.push(line);
+            }
+            let script = lines.join("\n");
+            if !script.trim().is_empty() {
+                run(script.trim());
+            }
+            if quit {
+                return Ok(());
+            }
+        }
+    })();
+    if created {
+        let _ = fs::remove_file(path);
+    }
+    result
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn serve_scripts_until_quit() {
+        let path = std::env::temp_dir().join(format!("adbc-cli-fifo-{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let writer = {
+            let path = path.clone();
+            std::thread::spawn(move || {
+                for script in ["SELECT 1;\n", "SELECT 2;\nSELECT 3\n", "\\quit\n"] {
+                    // Wait for the reader to create the FIFO and reopen it.
+                    let mut fifo = loop {
+                        match fs::metadata(&path) {
+                            Ok(_) => break fs::OpenOptions::new().write(true).open(&path).unwrap(),
+                            Err(_) => std::thread::sleep(std::time::Duration::from_millis(10)),
+                        }
+                    };
+                    fifo.write_all(script.as_bytes()).unwrap();
+                }
+            })
+        };
+
+        let mut scripts = Vec::new();
+        serve(&path, |script| scripts.push(script.to_string())).unwrap();
+        writer.join().unwrap();
+        // Scripts written in quick succession may arrive together.
+        let received = scripts.join("\n");
+        let received: Vec<&str> = received.lines().collect();
+        assert_eq!(received, ["SELECT 1;", "SELECT 2;", "SELECT 3"]);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,481 @@
+//! Rendering Snowflake `GEOGRAPHY` and `GEOMETRY` values in table output, for
+//! `--geo-format`.
+//!
+//! Depending on the session's `GEOGRAPHY_OUTPUT_FORMAT`, these columns arrive
+//! as GeoJSON text or as WKB, either binary or hex text. By default GeoJSON is
+//! written compactly on one line and WKB as WKT; `--geo-format` converts every
+//! value to one representation. Values in other forms (e.g. WKT, which is
+//! already legible) are written unchanged.
+
+use std::fmt::Write as _;
+
+use arrow_array::cast::AsArray;
+use arrow_array::Array;
+use arrow_schema::{DataType, Field};
+use clap::ValueEnum;
+use serde_json::{json, Value};
+
+/// How geospatial values are written in table output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum GeoFormat {
+    /// Compact GeoJSON, e.g. {"type":"Point","coordinates":[1,2]}.
+    Geojson,
+    /// Well-known text, e.g. POINT (1 2).
+    Wkt,
+    /// Well-known binary as hex.
+    Wkb,
+}
+
+/// Metadata keys naming a column's Snowflake type: the driver's, and the
+/// one in Snowflake's own Arrow results.
+const TYPE_KEYS: [&str; 2] = ["SNOWFLAKE_TYPE", "logicalType"];
+
+/// Whether `field` is a `GEOGRAPHY` or `GEOMETRY` column.
+pub fn is_geo(field: &Field) -> bool {
+    let encoded = matches!(
+        field.data_type(),
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Binary | DataType::LargeBinary
+    );
+    encoded
+        && TYPE_KEYS.iter().any(|key| {
+            field.metadata().get(*key).is_some_and(|value| {
+                value.eq_ignore_ascii_case("geography") || value.eq_ignore_ascii_case("geometry")
+            })
+        })
+}
+
+/// A geometry's coordinates: x, y, then z and m if present. An empty point
+/// has none.
+type Coord = Vec<f64>;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Geometry {
+    Point(Coord),
+    LineString(Vec<Coord>),
+    Polygon(Vec<Vec<Coord>>),
+    MultiPoint(Vec<Coord>),
+    MultiLineString(Vec<Vec<Coord>>),
+    MultiPolygon(Vec<Vec<Vec<Coord>>>),
+    Collection(Vec<Geometry>),
+}
+
+/// Reads WKB, ISO or PostGIS extended, in either byte order.
+struct WkbReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl WkbReader<'_> {
+    fn take(&mut self, n: usize) -> Option<&[u8]> {
+        let taken = self.bytes.get(self.pos..self.pos + n)?;
+        self.pos += n;
+        Some(taken)
+    }
+
+    fn u32(&mut self, little: bool) -> Option<u32> {
+        let bytes: [u8; 4] = self.take(4)?.try_into().ok()?;
+        Some(if little {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    fn f64(&mut self, little: bool) -> Option<f64> {
+        let bytes: [u8; 8] = self.take(8)?.try_into().ok()?;
+        Some(if little {
+            f64::from_le_bytes(bytes)
+        } else {
+            f64::from_be_bytes(bytes)
+        })
+    }
+
+    fn coord(&mut self, little: bool, dims: usize) -> Option<Coord> {
+        (0..dims).map(|_| self.f64(little)).collect()
+    }
+
+    fn coords(&mut self, little: bool, dims: usize) -> Option<Vec<Coord>> {
+        let count = self.u32(little)?;
+        (0..count).map(|_| self.coord(little, dims)).collect()
+    }
+
+    fn rings(&mut self, little: bool, dims: usize) -> Option<Vec<Vec<Coord>>> {
+        let count = self.u32(little)?;
+        (0..count).map(|_| self.coords(little, dims)).collect()
+    }
+
+    fn geometries(&mut self, little: bool) -> Option<Vec<Geometry>> {
+        let count = self.u32(little)?;
+        (0..count).map(|_| self.geometry()).collect()
+    }
+
+    fn geometry(&mut self) -> Option<Geometry> {
+        let little = match self.take(1)?[0] {
+            0 => false,
+            1 => true,
+            _ => return None,
+        };
+        let code = self.u32(little)?;
+        // PostGIS flags its extensions in the high bits, ISO adds thousands.
+        let (mut z, mut m) = (code & 0x8000_0000 != 0, code & 0x4000_0000 != 0);
+        if code & 0x2000_0000 != 0 {
+            self.u32(little)?;
+        }
+        let code = code & 0x0fff_ffff;
+        z |= matches!(code / 1000, 1 | 3);
+        m |= matches!(code / 1000, 2 | 3);
+        let dims = 2 + usize::from(z) + usize::from(m);
+        let members = |reader: &mut Self| reader.geometries(little);
+        Some(match code % 1000 {
+            1 => {
+                let coord = self.coord(little, dims)?;
+                // An empty point is written with NaN coordinates.
+                if coord.iter().all(|c| c.is_nan()) {
+                    Geometry::Point(Vec::new())
+                } else {
+                    Geometry::Point(coord)
+                }
+            }
+            2 => Geometry::LineString(self.coords(little, dims)?),
+            3 => Geometry::Polygon(self.rings(little, dims)?),
+            4 => Geometry::MultiPoint(
+                members(self)?
+                    .into_iter()
+                    .map(|member| match member {
+                        Geometry::Point(coord) => Some(coord),
+                        _ => None,
+                    })
+                    .collect::<Option<_>>()?,
+            ),
+            5 => Geometry::MultiLineString(
+                members(self)?
+                    .into_iter()
+                    .map(|member| match member {
+                        Geometry::LineString(coords) => Some(coords),
+                        _ => None,
+                    })
+                    .collect::<Option<_>>()?,
+            ),
+            6 => Geometry::MultiPolygon(
+                members(self)?
+                    .into_iter()
+                    .map(|member| match member {
+                        Geometry::Polygon(rings) => Some(rings),
+                        _ => None,
+                    })
+                    .collect::<Option<_>>()?,
+            ),
+            7 => Geometry::Collection(members(self)?),
+            _ => return None,
+        })
+    }
+}
+
+/// Parse one geometry filling all of `bytes`.
+fn parse_wkb(bytes: &[u8]) -> Option<Geometry> {
+    let mut reader = WkbReader { bytes, pos: 0 };
+    let geometry = reader.geometry()?;
+    (reader.pos == bytes.len()).then_some(geometry)
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if text.len() % 2 != 0 || !text.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|idx| u8::from_str_radix(&text[idx..idx + 2], 16).ok())
+        .collect()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{:02x}", byte);
+        hex
+    })
+}
+
+fn json_coord(value: &Value) -> Option<Coord> {
+    value.as_array()?.iter().map(Value::as_f64).collect()
+}
+
+fn json_coords(value: &Value) -> Option<Vec<Coord>> {
+    value.as_array()?.iter().map(json_coord).collect()
+}
+
+fn json_rings(value: &Value) -> Option<Vec<Vec<Coord>>> {
+    value.as_array()?.iter().map(json_coords).collect()
+}
+
+fn parse_geojson(value: &Value) -> Option<Geometry> {
+    let coordinates = value.get("coordinates");
+    Some(match value.get("type")?.as_str()? {
+        "Point" => Geometry::Point(json_coord(coordinates?)?),
+        "LineString" => Geometry::LineString(json_coords(coordinates?)?),
+        "Polygon" => Geometry::Polygon(json_rings(coordinates?)?),
+        "MultiPoint" => Geometry::MultiPoint(json_coords(coordinates?)?),
+        "MultiLineString" => Geometry::MultiLineString(json_rings(coordinates?)?),
+        "MultiPolygon" => Geometry::MultiPolygon(
+            coordinates?
+                .as_array()?
+                .iter()
+                .map(json_rings)
+                .collect::<Option<_>>()?,
+        ),
+        "GeometryCollection" => Geometry::Collection(
+            value
+                .get("geometries")?
+                .as_array()?
+                .iter()
+                .map(parse_geojson)
+                .collect::<Option<_>>()?,
+        ),
+        _ => return None,
+    })
+}
+
+impl Geometry {
+    /// The number of coordinates of the first position, or 2 if empty.
+    fn dims(&self) -> usize {
+        let first = match self {
+            Geometry::Point(coord) => Some(coord),
+            Geometry::LineString(coords) | Geometry::MultiPoint(coords) => coords.first(),
+            Geometry::Polygon(rings) | Geometry::MultiLineString(rings) => {
+                rings.iter().flatten().next()
+            }
+            Geometry::MultiPolygon(polygons) => polygons.iter().flatten().flatten().next(),
+            Geometry::Collection(members) => return members.first().map_or(2, Self::dims),
+        };
+        first.map_or(2, |coord| coord.len().clamp(2, 4))
+    }
+
+    fn wkt_name(&self) -> &'static str {
+        match self {
+            Geometry::Point(_) => "POINT",
+            Geometry::LineString(_) => "LINESTRING",
+            Geometry::Polygon(_) => "POLYGON",
+            Geometry::MultiPoint(_) => "MULTIPOINT",
+            Geometry::MultiLineString(_) => "MULTILINESTRING",
+            Geometry::MultiPolygon(_) => "MULTIPOLYGON",
+            Geometry::Collection(_) => "GEOMETRYCOLLECTION",
+        }
+    }
+
+    fn wkt(&self) -> String {
+        fn coord(coord: &Coord) -> String {
+            let values: Vec<String> = coord.iter().map(f64::to_string).collect();
+            values.join(" ")
+        }
+        fn list<T>(items: &[T], item: impl Fn(&T) -> String) -> String {
+            if items.is_empty() {
+                return "EMPTY".to_string();
+            }
+            let items: Vec<String> = items.iter().map(item).collect();
+            format!("({})", items.join(", "))
+        }
+        let coords = |coords: &Vec<Coord>| list(coords, coord);
+        let rings = |rings: &Vec<Vec<Coord>>| list(rings, coords);
+        let body = match self {
+            Geometry::Point(c) if c.is_empty() => "EMPTY".to_string(),
+            Geometry::Point(c) => format!("({})", coord(c)),
+            Geometry::LineString(c) => coords(c),
+            Geometry::Polygon(r) => rings(r),
+            Geometry::MultiPoint(points) => list(points, |c| format!("({})", coord(c))),
+            Geometry::MultiLineString(r) => rings(r),
+            Geometry::MultiPolygon(polygons) => list(polygons, rings),
+            Geometry::Collection(members) => list(members, Geometry::wkt),
+        };
+        let dims = match self.dims() {
+            3 => " Z",
+            4 => " ZM",
+            _ => "",
+        };
+        format!("{}{} {}", self.wkt_name(), dims, body)
+    }
+
+    fn geojson(&self) -> Value {
+        match self {
+            Geometry::Point(c) => json!({"type": "Point", "coordinates": c}),
+            Geometry::LineString(c) => json!({"type": "LineString", "coordinates": c}),
+            Geometry::Polygon(r) => json!({"type": "Polygon", "coordinates": r}),
+            Geometry::MultiPoint(c) => json!({"type": "MultiPoint", "coordinates": c}),
+            Geometry::MultiLineString(r) => json!({"type": "MultiLineString", "coordinates": r}),
+            Geometry::MultiPolygon(p) => json!({"type": "MultiPolygon", "coordinates": p}),
+            Geometry::Collection(members) => json!({
+                "type": "GeometryCollection",
+                "geometries": members.iter().map(Geometry::geojson).collect::<Vec<_>>(),
+            }),
+        }
+    }
+
+    /// ISO WKB, little-endian.
+    fn wkb(&self, dims: usize, out: &mut Vec<u8>) {
+        let code = match self {
+            Geometry::Point(_) => 1,
+            Geometry::LineString(_) => 2,
+            Geometry::Polygon(_) => 3,
+            Geometry::MultiPoint(_) => 4,
+            Geometry::MultiLineString(_) => 5,
+            Geometry::MultiPolygon(_) => 6,
+            Geometry::Collection(_) => 7,
+        };
+        let offset = match dims {
+            3 => 1000,
+            4 => 3000,
+            _ => 0,
+        };
+        out.push(1);
+        out.extend((code + offset as u32).to_le_bytes());
+        let count = |out: &mut Vec<u8>, n: usize| out.extend((n as u32).to_le_bytes());
+        let coord = |out: &mut Vec<u8>, c: &Coord| {
+            for idx in 0..dims {
+                out.extend(c.get(idx).copied().unwrap_or(f64::NAN).to_le_bytes());
+            }
+        };
+        let coords = |out: &mut Vec<u8>, c: &Vec<Coord>| {
+            count(out, c.len());
+            c.iter().for_each(|c| coord(out, c));
+        };
+        let rings = |out: &mut Vec<u8>, r: &Vec<Vec<Coord>>| {
+            count(out, r.len());
+            r.iter().for_each(|c| coords(out, c));
+        };
+        match self {
+            Geometry::Point(c) => coord(out, c),
+            Geometry::LineString(c) => coords(out, c),
+            Geometry::Polygon(r) => rings(out, r),
+            Geometry::MultiPoint(points) => {
+                count(out, points.len());
+                for point in points {
+                    Geometry::Point(point.clone()).wkb(dims, out);
+                }
+            }
+            Geometry::MultiLineString(lines) => {
+                count(out, lines.len());
+                for line in lines {
+                    Geometry::LineString(line.clone()).wkb(dims, out);
+                }
+            }
+            Geometry::MultiPolygon(polygons) => {
+                count(out, polygons.len());
+                for polygon in polygons {
+                    Geometry::Polygon(polygon.clone()).wkb(dims, out);
+                }
+            }
+            Geometry::Collection(members) => {
+                count(out, members.len());
+                for member in members {
+                    member.wkb(member.dims(), out);
+                }
+            }
+        }
+    }
+
+    fn wkb_hex(&self) -> String {
+        let mut bytes = Vec::new();
+        self.wkb(self.dims(), &mut bytes);
+        hex(&bytes)
+    }
+}
+
+/// The value at `row_idx` of a geospatial column, written as `format` asks,
+/// or by default as compact GeoJSON if it's GeoJSON and as WKT if it's WKB.
+pub fn format_value(col: &dyn Array, row_idx: usize, format: Option<GeoFormat>) -> String {
+    let (text, bytes) = match col.data_type() {
+        DataType::Binary => (None, Some(col.as_binary::<i32>().value(row_idx).to_vec())),
+        DataType::LargeBinary => (None, Some(col.as_binary::<i64>().value(row_idx).to_vec())),
+        DataType::Utf8 => (Some(col.as_string::<i32>().value(row_idx)), None),
+        _ => (Some(col.as_string::<i64>().value(row_idx)), None),
+    };
+
+    if let Some(json) = text
+        .filter(|text| text.trim_start().starts_with('{'))
+        .and_then(|text| serde_json::from_str::<Value>(text).ok())
+    {
+        return match (format, parse_geojson(&json)) {
+            (None | Some(GeoFormat::Geojson), _) => json.to_string(),
+            (Some(GeoFormat::Wkt), Some(geometry)) => geometry.wkt(),
+            (Some(GeoFormat::Wkb), Some(geometry)) => geometry.wkb_hex(),
+            (_, None) => json.to_string(),
+        };
+    }
+    let wkb = bytes
+        .as_deref()
+        .map(Vec::from)
+        .or_else(|| text.and_then(|text| decode_hex(text.trim())));
+    match (format, wkb.as_deref().and_then(parse_wkb)) {
+        (None | Some(GeoFormat::Wkt), Some(geometry)) => geometry.wkt(),
+        (Some(GeoFormat::Geojson), Some(geometry)) => geometry.geojson().to_string(),
+        (Some(GeoFormat::Wkb), Some(geometry)) => geometry.wkb_hex(),
+        // Anything else, e.g. WKT, is written as it is.
+        (_, None) => text.map_or_else(|| hex(&bytes.unwrap_or_default()), str::to_string),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use arrow_array::{BinaryArray, StringArray};
+
+    use super::*;
+
+    #[test]
+    fn render_geojson_and_wkb() {
+        let metadata = HashMap::from([("SNOWFLAKE_TYPE".to_string(), "geography".to_string())]);
+        assert!(is_geo(
+            &Field::new("G", DataType::Binary, true).with_metadata(metadata.clone())
+        ));
+        assert!(!is_geo(&Field::new("G", DataType::Binary, true)));
+        assert!(!is_geo(
+            &Field::new("G", DataType::Int64, true).with_metadata(metadata)
+        ));
+
+        let geojson = StringArray::from(vec![
+            "{\n  \"coordinates\": [\n    -122.35,\n    37.55\n  ],\n  \"type\": \"Point\"\n}",
+            "POINT(1 2)",
+        ]);
+        assert_eq!(
+            format_value(&geojson, 0, None),
+            r#"{"coordinates":[-122.35,37.55],"type":"Point"}"#
+        );
+        assert_eq!(
+            format_value(&geojson, 0, Some(GeoFormat::Wkt)),
+            "POINT (-122.35 37.55)"
+        );
+        assert_eq!(
+            format_value(&geojson, 1, Some(GeoFormat::Wkb)),
+            "POINT(1 2)"
+        );
+
+        // LINESTRING (1 2, 3 4), big-endian, as bytes and as hex text.
+        let mut wkb = vec![0, 0, 0, 0, 2, 0, 0, 0, 2];
+        for value in [1.0f64, 2.0, 3.0, 4.0] {
+            wkb.extend(value.to_be_bytes());
+        }
+        let binary = BinaryArray::from(vec![&wkb[..], &b"\x01\x09"[..]]);
+        assert_eq!(format_value(&binary, 0, None), "LINESTRING (1 2, 3 4)");
+        assert_eq!(
+            format_value(&binary, 0, Some(GeoFormat::Geojson)),
+            r#"{"coordinates":[[1.0,2.0],[3.0,4.0]],"type":"LineString"}"#
+        );
+        assert_eq!(format_value(&binary, 1, None), "0109");
+
+        let hex = hex(&wkb);
+        let text: Arc<dyn Array> = Arc::new(StringArray::from(vec![hex.to_uppercase()]));
+        let little = format_value(text.as_ref(), 0, Some(GeoFormat::Wkb));
+        assert!(little.starts_with("0102000000"));
+        let little = StringArray::from(vec![little]);
+        assert_eq!(format_value(&little, 0, None), "LINESTRING (1 2, 3 4)");
+
+        let collection = StringArray::from(vec![
+            r#"{"type":"GeometryCollection","geometries":[{"type":"Point","coordinates":[]},
+               {"type":"MultiPoint","coordinates":[[0,0,1],[1,1,2]]}]}"#,
+        ]);
+        assert_eq!(
+            format_value(&collection, 0, Some(GeoFormat::Wkt)),
+            "GEOMETRYCOLLECTION (POINT EMPTY, MULTIPOINT Z ((0 0 1), (1 1 2)))"
+        );
+    }
+}
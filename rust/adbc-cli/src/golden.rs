@@ -0,0 +1,216 @@
+//! Checking a query's result against a stored golden file, for
+//! `--expect-file`, so CI can assert that a query produces exactly the
+//! expected output.
+//!
+//! Arrow and Parquet files keep their types, so the result must match the
+//! file's schema as well as its values. A CSV file keeps only text, so the
+//! result is first written as CSV, as `--output csv` would write it, and the
+//! two are compared as text. Either way every value is compared in full,
+//! whatever its type.
+
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use arrow_array::{RecordBatch, RecordBatchIterator, RecordBatchReader};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+use crate::diff::{self, Diff};
+use crate::output::{self, OutputFormat, RenderOptions};
+
+/// The most differing rows shown, as for `diff`.
+pub const MAX_DIFFS: usize = 10;
+
+fn collect(reader: impl RecordBatchReader) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+    let schema = reader.schema();
+    let batches = reader.collect::<Result<Vec<_>, _>>()?;
+    Ok((schema, batches))
+}
+
+/// `schema` with every column as text.
+fn text_schema(schema: &Schema) -> SchemaRef {
+    Arc::new(Schema::new(
+        schema
+            .fields()
+            .iter()
+            .map(|field| Field::new(field.name(), DataType::Utf8, true))
+            .collect::<Vec<_>>(),
+    ))
+}
+
+/// Read CSV, with a header row, keeping every column as text.
+fn read_csv_text(
+    mut data: impl std::io::Read + std::io::Seek,
+) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+    let format = arrow_csv::reader::Format::default().with_header(true);
+    let (inferred, _) = format.infer_schema(&mut data, Some(0))?;
+    data.rewind()?;
+    read_csv_with(data, text_schema(&inferred))
+}
+
+/// Read CSV, with a header row if there's any data, as `schema`.
+fn read_csv_with(
+    data: impl std::io::Read,
+    schema: SchemaRef,
+) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+    let format = arrow_csv::reader::Format::default().with_header(true);
+    let reader = arrow_csv::ReaderBuilder::new(schema)
+        .with_format(format)
+        .build(data)?;
+    collect(reader)
+}
+
+/// Read the golden file at `path`, in the format its extension names.
+fn read_expected(path: &Path, format: OutputFormat) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    match format {
+        OutputFormat::Csv => read_csv_text(file),
+        OutputFormat::ArrowFile => collect(arrow_ipc::reader::FileReader::try_new(file, None)?),
+        OutputFormat::ArrowStream => collect(arrow_ipc::reader::StreamReader::try_new(file, None)?),
+        OutputFormat::Parquet => collect(ParquetRecordBatchReaderBuilder::try_new(file)?.build()?),
+        _ => unreachable!("checked by compare"),
+    }
+}
+
+/// Compare a result with the golden file at `path`. With `sorted`, rows are
+/// sorted first, for queries without a deterministic order.
+pub fn compare(actual: (&SchemaRef, &[RecordBatch]), path: &Path, sorted: bool) -> Result<Diff> {
+    let format = OutputFormat::from_path(path).filter(|format| {
+        matches!(
+            format,
+            OutputFormat::Csv
+                | OutputFormat::ArrowFile
+                | OutputFormat::ArrowStream
+                | OutputFormat::Parquet
+        )
+    });
+    let Some(format) = format else {
+        bail!(
+            "Unsupported expected file: {} (expected .csv, .arrow, .arrows, or .parquet)",
+            path.display()
+        );
+    };
+    let (expected_schema, expected) = read_expected(path, format)
+        .with_context(|| format!("Failed to read expected file {}", path.display()))?;
+
+    let (actual_schema, actual) = if format == OutputFormat::Csv {
+        let mut csv = Vec::new();
+        let reader = RecordBatchIterator::new(actual.1.iter().cloned().map(Ok), actual.0.clone());
+        output::write_output(reader, format, &RenderOptions::default(), &mut csv)?;
+        // Take the columns from the result rather than the CSV, which has no
+        // header when there are no rows.
+        read_csv_with(std::io::Cursor::new(csv), text_schema(actual.0))?
+    } else {
+        (actual.0.clone(), actual.1.to_vec())
+    };
+    diff::compare(
+        (&expected_schema, &expected),
+        (&actual_schema, &actual),
+        sorted,
+        MAX_DIFFS,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{BinaryArray, Int64Array, StringArray};
+
+    use super::*;
+
+    fn result(ids: Vec<i64>, names: Vec<Option<&str>>) -> (SchemaRef, Vec<RecordBatch>) {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("ID", DataType::Int64, false),
+            Field::new("NAME", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(ids)),
+                Arc::new(StringArray::from(names)),
+            ],
+        )
+        .unwrap();
+        (schema, vec![batch])
+    }
+
+    #[test]
+    fn compare_with_golden_files() {
+        let path = std::env::temp_dir().join(format!("adbc-cli-golden-{}.csv", std::process::id()));
+        std::fs::write(&path, "ID,NAME\n1,a\n2,\"b, c\"\n3,\n").unwrap();
+        let (schema, batches) = result(vec![1, 2, 3], vec![Some("a"), Some("b, c"), None]);
+        assert!(compare((&schema, &batches), &path, false)
+            .unwrap()
+            .is_match());
+
+        let (schema, batches) = result(vec![3, 1, 2], vec![None, Some("a"), Some("b, c")]);
+        assert!(!compare((&schema, &batches), &path, false)
+            .unwrap()
+            .is_match());
+        assert!(compare((&schema, &batches), &path, true)
+            .unwrap()
+            .is_match());
+
+        let (schema, batches) = result(vec![1, 2, 3], vec![Some("a"), Some("b"), None]);
+        let diff = compare((&schema, &batches), &path, false).unwrap();
+        assert_eq!(diff.total_row_differences, 1);
+        assert_eq!(diff.rows[0].row, 2);
+
+        // An empty result matches a header-only file.
+        std::fs::write(&path, "ID,NAME\n").unwrap();
+        assert!(compare((&schema, &[]), &path, false).unwrap().is_match());
+        std::fs::remove_file(&path).unwrap();
+
+        // Arrow files keep types, so the schema must match too.
+        let path = path.with_extension("arrow");
+        let (schema, batches) = result(vec![1], vec![Some("a")]);
+        let mut arrow = Vec::new();
+        let reader = RecordBatchIterator::new(batches.clone().into_iter().map(Ok), schema.clone());
+        output::write_output(
+            reader,
+            OutputFormat::ArrowFile,
+            &RenderOptions::default(),
+            &mut arrow,
+        )
+        .unwrap();
+        std::fs::write(&path, arrow).unwrap();
+        assert!(compare((&schema, &batches), &path, false)
+            .unwrap()
+            .is_match());
+        let text = Arc::new(Schema::new(vec![
+            Field::new("ID", DataType::Utf8, false),
+            Field::new("NAME", DataType::Utf8, true),
+        ]));
+        let diff = compare((&text, &[]), &path, false).unwrap();
+        assert_eq!(diff.schema_differences, ["column 1: ID Int64 vs ID Utf8"]);
+
+        // Values are compared whatever their type.
+        let binary = |bytes: &[u8]| {
+            let schema = Arc::new(Schema::new(vec![Field::new("B", DataType::Binary, true)]));
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(BinaryArray::from(vec![bytes]))],
+            )
+            .unwrap();
+            (schema, vec![batch])
+        };
+        let (schema, batches) = binary(b"expected");
+        let mut arrow = Vec::new();
+        let reader = RecordBatchIterator::new(batches.into_iter().map(Ok), schema);
+        output::write_output(
+            reader,
+            OutputFormat::ArrowFile,
+            &RenderOptions::default(),
+            &mut arrow,
+        )
+        .unwrap();
+        std::fs::write(&path, arrow).unwrap();
+        let (schema, batches) = binary(b"actual");
+        let diff = compare((&schema, &batches), &path, false).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(diff.total_row_differences, 1);
+
+        assert!(compare((&schema, &batches), Path::new("golden.txt"), false).is_err());
+    }
+}
@@ -0,0 +1,216 @@
+//! Actionable hints for common Snowflake errors, shown with `--explain-errors`.
+
+use adbc_core::error::{Error as AdbcError, Status};
+
+/// Returns the ADBC error underlying `err`, if any.
+pub fn adbc_error(err: &anyhow::Error) -> Option<&AdbcError> {
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<AdbcError>())
+}
+
+/// Returns the SQLSTATE of `err` as a string, or `None` if it is unset.
+pub fn sqlstate(err: &AdbcError) -> Option<String> {
+    let state: String = err
+        .sqlstate
+        .iter()
+        .take_while(|&&c| c != 0)
+        .map(|&c| c as u8 as char)
+        .collect();
+    (!state.is_empty()).then_some(state)
+}
+
+/// Snowflake vendor codes for an expired session or authentication token
+/// (e.g. a JWT that outlived its validity), which a new login fixes.
+const TOKEN_EXPIRED_CODES: &[i32] = &[390112, 390114];
+
+/// Returns true if `err` means the session's authentication token expired.
+pub fn is_token_expired(err: &anyhow::Error) -> bool {
+    adbc_error(err).is_some_and(|err| TOKEN_EXPIRED_CODES.contains(&err.vendor_code))
+}
+
+/// Snowflake vendor codes for a login refused for multi-factor authentication:
+/// a TOTP passcode required (394508), a Duo push denied (390120), or an
+/// incorrect passcode (390127).
+const MFA_CODES: &[i32] = &[394508, 390120, 390127];
+
+/// Returns true if `err` means logging in needs a multi-factor passcode or
+/// approval. The driver doesn't always keep Snowflake's vendor code for login
+/// failures, so the message is checked as well.
+pub fn is_mfa_required(err: &anyhow::Error) -> bool {
+    adbc_error(err).is_some_and(|err| {
+        let message = err.message.to_ascii_lowercase();
+        MFA_CODES.contains(&err.vendor_code)
+            || ["mfa", "multi-factor", "totp", "passcode", "duo security"]
+                .iter()
+                .any(|pattern| message.contains(pattern))
+    })
+}
+
+/// Returns true if `err` means there is no running warehouse, e.g. because it
+/// is suspended with auto-resume disabled.
+pub fn is_warehouse_suspended(err: &anyhow::Error) -> bool {
+    adbc_error(err)
+        .is_some_and(|err| err.vendor_code == 606 || sqlstate(err).as_deref() == Some("57P03"))
+}
+
+/// Snowflake's vendor code for a statement canceled after reaching its
+/// statement or warehouse timeout.
+const STATEMENT_TIMEOUT_CODE: i32 = 630;
+
+/// Returns true if `err` means the statement was canceled for reaching its
+/// timeout.
+pub fn is_statement_timeout(err: &anyhow::Error) -> bool {
+    adbc_error(err).is_some_and(|err| err.vendor_code == STATEMENT_TIMEOUT_CODE)
+}
+
+/// Returns the timeout, in seconds, that a statement timeout error reports
+/// ("...reached its statement or warehouse timeout of 60 second(s)...").
+pub fn statement_timeout_secs(err: &anyhow::Error) -> Option<u64> {
+    let err = adbc_error(err).filter(|err| err.vendor_code == STATEMENT_TIMEOUT_CODE)?;
+    let (_, rest) = err.message.split_once("timeout of ")?;
+    rest.split_whitespace().next()?.parse().ok()
+}
+
+/// Returns a short hint for `err` based on its Snowflake vendor code or
+/// SQLSTATE, or `None` if the error isn't one we recognize.
+pub fn hint(err: &anyhow::Error, role: Option<&str>) -> Option<String> {
+    let err = adbc_error(err)?;
+    let (role, role_lower) = match role {
+        Some(role) => (format!("Role {}", role), format!("role {}", role)),
+        None => (
+            "Your current role".to_string(),
+            "your current role".to_string(),
+        ),
+    };
+    let sqlstate = sqlstate(err);
+
+    let hint = match (err.vendor_code, sqlstate.as_deref()) {
+        (2003 | 2043, _) | (_, Some("42S02")) => format!(
+            "The object does not exist, or {} is not authorized to see it. Check the \
+             database and schema, fully qualify the name (DB.SCHEMA.OBJECT), or set \
+             `role` in the profile to one with access.",
+            role_lower
+        ),
+        (3001, _) | (_, Some("42501")) => format!(
+            "{} may lack the required privilege (e.g. SELECT); set `role` in the profile \
+             to one that has it.",
+            role
+        ),
+        (606, _) | (_, Some("57P03")) => "No active warehouse. Set `warehouse` in the profile, \
+             or the warehouse may be suspended with auto-resume disabled; run \
+             `ALTER WAREHOUSE <name> RESUME`."
+            .to_string(),
+        (390189, _) => format!(
+            "{} is not granted to this user; check `role` in the profile.",
+            role
+        ),
+        (1003, _) | (_, Some("42000")) => {
+            "SQL syntax error; check the statement near the reported line and position.".to_string()
+        }
+        _ if err.status == Status::Unauthenticated => {
+            "Authentication failed; check `user` and `password` or `private_key` in the profile."
+                .to_string()
+        }
+        _ => return None,
+    };
+    Some(hint)
+}
+
+/// Flatten `err` into a single message with a hint appended, if one applies.
+pub fn annotate(err: anyhow::Error, role: Option<&str>) -> anyhow::Error {
+    match hint(&err, role) {
+        Some(hint) => anyhow::anyhow!("{:#}\nHint: {}", err, hint),
+        None => err,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::raw::c_char;
+
+    use anyhow::Context;
+
+    use super::*;
+
+    fn snowflake_error(vendor_code: i32, sqlstate: &str) -> anyhow::Error {
+        let mut state = [0 as c_char; 5];
+        for (dst, src) in state.iter_mut().zip(sqlstate.bytes()) {
+            *dst = src as c_char;
+        }
+        let err = AdbcError {
+            vendor_code,
+            sqlstate: state,
+            ..AdbcError::with_message_and_status("boom", Status::Unknown)
+        };
+        Err::<(), _>(err)
+            .context("Failed to execute query")
+            .unwrap_err()
+    }
+
+    #[test]
+    fn hints_for_known_errors() {
+        let err = snowflake_error(2003, "02000");
+        assert!(hint(&err, Some("ANALYST"))
+            .unwrap()
+            .contains("role ANALYST"));
+
+        let err = snowflake_error(3001, "42501");
+        assert!(hint(&err, None).unwrap().starts_with("Your current role"));
+
+        let err = snowflake_error(606, "57P03");
+        assert!(hint(&err, None).unwrap().contains("RESUME"));
+    }
+
+    #[test]
+    fn no_hint_for_unknown_errors() {
+        assert!(hint(&snowflake_error(9999, "HY000"), None).is_none());
+        assert!(hint(&anyhow::anyhow!("not an ADBC error"), None).is_none());
+    }
+
+    #[test]
+    fn detects_expired_tokens() {
+        assert!(is_token_expired(&snowflake_error(390114, "08001")));
+        assert!(!is_token_expired(&snowflake_error(2003, "02000")));
+        assert!(is_warehouse_suspended(&snowflake_error(606, "57P03")));
+        assert!(!is_warehouse_suspended(&snowflake_error(390114, "08001")));
+    }
+
+    #[test]
+    fn detects_mfa_required() {
+        let err = anyhow::Error::new(AdbcError::with_message_and_status(
+            "Failed to authenticate: MFA with TOTP is required. To authenticate, provide both \
+             your password and a current TOTP passcode.",
+            Status::Unauthenticated,
+        ));
+        assert!(is_mfa_required(&err));
+        assert!(is_mfa_required(&snowflake_error(390120, "08004")));
+        assert!(!is_mfa_required(&snowflake_error(390100, "08004")));
+    }
+
+    #[test]
+    fn detects_statement_timeouts() {
+        let err = anyhow::Error::new(AdbcError {
+            vendor_code: 630,
+            ..AdbcError::with_message_and_status(
+                "000630 (57014): Statement reached its statement or warehouse timeout of 60 \
+                 second(s) and was canceled.",
+                Status::Unknown,
+            )
+        });
+        assert!(is_statement_timeout(&err));
+        assert_eq!(statement_timeout_secs(&err), Some(60));
+
+        let err = snowflake_error(630, "57014");
+        assert!(is_statement_timeout(&err));
+        assert_eq!(statement_timeout_secs(&err), None);
+        assert!(!is_statement_timeout(&snowflake_error(606, "57P03")));
+    }
+
+    #[test]
+    fn annotate_appends_hint() {
+        let err = annotate(snowflake_error(606, ""), None);
+        let message = err.to_string();
+        assert!(message.starts_with("Failed to execute query: "));
+        assert!(message.contains("\nHint: No active warehouse"));
+    }
+}
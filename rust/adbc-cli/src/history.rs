@@ -0,0 +1,162 @@
+//! Appending benchmark runs to a results file, for `benchmark --results-file`.
+//!
+//! Each run becomes one JSON line, so a file built up over many runs is a
+//! time series that `jq`, pandas, or DuckDB can read directly. The fields are
+//! versioned by `schema_version`: new fields may be added, but existing ones
+//! keep their names and units (times are milliseconds).
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::BenchmarkResult;
+
+/// The version of the record layout, bumped if a field changes meaning.
+const SCHEMA_VERSION: u32 = 1;
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// A stable hash of the query text (64-bit FNV-1a, in hex), to group runs of
+/// the same query without storing it.
+fn query_hash(query: &str) -> String {
+    let hash = query.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// The commit checked out in the working directory, if it's a git repository.
+fn git_commit() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let commit = String::from_utf8(output.stdout).ok()?;
+    let commit = commit.trim();
+    (output.status.success() && !commit.is_empty()).then(|| commit.to_string())
+}
+
+/// One benchmark run as written to the results file.
+#[derive(Debug, Serialize)]
+pub struct Record {
+    schema_version: u32,
+    timestamp: String,
+    query_hash: String,
+    git_commit: Option<String>,
+    client: String,
+    iterations: u32,
+    concurrency: u32,
+    reuse_connection: bool,
+    rows: Option<usize>,
+    total_ms: f64,
+    avg_ms: f64,
+    min_ms: f64,
+    max_ms: f64,
+    p95_ms: f64,
+    times_ms: Vec<f64>,
+    prewarm_ms: Option<f64>,
+    connect_ms: Option<f64>,
+    wall_ms: Option<f64>,
+}
+
+impl Record {
+    pub fn new(
+        result: &BenchmarkResult,
+        query: &str,
+        concurrency: u32,
+        reuse_connection: bool,
+    ) -> Self {
+        Record {
+            schema_version: SCHEMA_VERSION,
+            timestamp: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            query_hash: query_hash(query),
+            git_commit: git_commit(),
+            client: result.client.clone(),
+            iterations: result.iterations,
+            concurrency,
+            reuse_connection,
+            rows: result.rows,
+            total_ms: millis(result.total_time),
+            avg_ms: millis(result.avg_time),
+            min_ms: millis(result.min_time),
+            max_ms: millis(result.max_time),
+            p95_ms: millis(result.p95_time),
+            times_ms: result.times.iter().copied().map(millis).collect(),
+            prewarm_ms: result.prewarm_time.map(millis),
+            connect_ms: result.connect_time.map(millis),
+            wall_ms: result.wall_time.map(millis),
+        }
+    }
+}
+
+/// Append `record` to `path` as a JSON line, creating the file if needed.
+/// The line goes out in a single write to a file opened for appending, so
+/// runs finishing at the same time don't interleave their lines.
+pub fn append(path: &Path, record: &Record) -> Result<()> {
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .with_context(|| format!("Failed to append to results file: {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn append_results() {
+        assert_eq!(query_hash(""), "cbf29ce484222325");
+        assert_eq!(query_hash("SELECT 1"), query_hash("SELECT 1"));
+        assert_ne!(query_hash("SELECT 1"), query_hash("SELECT 2"));
+
+        let time = Duration::from_millis(250);
+        let result = BenchmarkResult {
+            client: "adbc".to_string(),
+            iterations: 2,
+            total_time: time * 2,
+            avg_time: time,
+            min_time: time,
+            max_time: time,
+            p95_time: time,
+            rows: Some(10),
+            row_counts: vec![5, 5],
+            times: vec![time, time],
+            phases: Vec::new(),
+            prewarm_time: None,
+            connect_time: None,
+            wall_time: None,
+        };
+        let path = std::env::temp_dir().join(format!("adbc-cli-results-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        for _ in 0..2 {
+            append(&path, &Record::new(&result, "SELECT 1", 1, false)).unwrap();
+        }
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let lines: Vec<serde_json::Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["schema_version"], 1);
+        assert_eq!(lines[0]["client"], "adbc");
+        assert_eq!(lines[0]["avg_ms"], 250.0);
+        assert_eq!(lines[0]["times_ms"], serde_json::json!([250.0, 250.0]));
+        assert_eq!(lines[0]["query_hash"], query_hash("SELECT 1"));
+        assert!(lines[1]["prewarm_ms"].is_null());
+    }
+}
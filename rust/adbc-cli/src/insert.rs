@@ -0,0 +1,158 @@
+//! Rendering results as SQL `INSERT` statements, for `--output insert`, to
+//! copy small results between databases or seed fixtures.
+//!
+//! Each row becomes one `INSERT INTO <table> VALUES (...);` line, written as
+//! its batch arrives. Values are written as literals of their type: numbers
+//! as they are, strings quoted, dates, times, and timestamps as typed
+//! literals (`TIMESTAMP_TZ` with the offset for zoned timestamps,
+//! `TIMESTAMP_NTZ` otherwise), binary as hex, and nulls as `NULL`.
+
+use std::io::Write;
+
+use anyhow::Result;
+use arrow_array::{Array, RecordBatchReader};
+use arrow_cast::display::{ArrayFormatter, FormatOptions};
+use arrow_schema::DataType;
+
+use crate::sql;
+
+/// The SQL literal for the value at `row_idx` of `col`, whose display by
+/// Arrow is `display`.
+fn literal(col: &dyn Array, row_idx: usize, display: String) -> String {
+    if col.is_null(row_idx) {
+        return "NULL".to_string();
+    }
+    match col.data_type() {
+        DataType::Boolean => display.to_ascii_uppercase(),
+        DataType::Float16 | DataType::Float32 | DataType::Float64 => {
+            // NaN and infinities have no numeric literal, but cast from strings.
+            if display.parse::<f64>().is_ok_and(f64::is_finite) {
+                display
+            } else {
+                sql::string_literal(&display)
+            }
+        }
+        dt if dt.is_integer() => display,
+        DataType::Decimal128(_, _) | DataType::Decimal256(_, _) => display,
+        DataType::Date32 | DataType::Date64 => format!("DATE '{}'", display),
+        DataType::Time32(_) | DataType::Time64(_) => format!("TIME '{}'", display),
+        // A plain TIMESTAMP is whatever TIMESTAMP_TYPE_MAPPING says, so name
+        // the type to keep a zoned value's offset.
+        DataType::Timestamp(_, Some(_)) => format!("TIMESTAMP_TZ '{}'", display),
+        DataType::Timestamp(_, None) => format!("TIMESTAMP_NTZ '{}'", display),
+        DataType::Binary
+        | DataType::LargeBinary
+        | DataType::BinaryView
+        | DataType::FixedSizeBinary(_) => format!("X'{}'", display),
+        _ => sql::string_literal(&display),
+    }
+}
+
+/// Write each row of `reader` as an `INSERT` into `table`.
+pub fn write_inserts(
+    reader: impl RecordBatchReader,
+    table: &str,
+    handle: &mut dyn Write,
+) -> Result<()> {
+    let options = FormatOptions::default()
+        .with_timestamp_format(Some("%Y-%m-%d %H:%M:%S%.f"))
+        .with_timestamp_tz_format(Some("%Y-%m-%d %H:%M:%S%.f %:z"));
+    for batch in reader {
+        let batch = batch?;
+        let formatters = batch
+            .columns()
+            .iter()
+            .map(|col| ArrayFormatter::try_new(col.as_ref(), &options))
+            .collect::<Result<Vec<_>, _>>()?;
+        for row_idx in 0..batch.num_rows() {
+            let values: Vec<String> = batch
+                .columns()
+                .iter()
+                .zip(&formatters)
+                .map(|(col, formatter)| {
+                    literal(col.as_ref(), row_idx, formatter.value(row_idx).to_string())
+                })
+                .collect();
+            writeln!(
+                handle,
+                "INSERT INTO {} VALUES ({});",
+                table,
+                values.join(", ")
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::{
+        BinaryArray, BooleanArray, Date32Array, Decimal128Array, Float64Array, Int64Array,
+        RecordBatch, RecordBatchIterator, StringArray, TimestampMicrosecondArray,
+    };
+    use arrow_schema::{Field, Schema, TimeUnit};
+
+    use super::*;
+
+    #[test]
+    fn write_rows_as_inserts() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("ID", DataType::Int64, true),
+            Field::new("NAME", DataType::Utf8, true),
+            Field::new("PRICE", DataType::Decimal128(10, 2), true),
+            Field::new("RATIO", DataType::Float64, true),
+            Field::new("ACTIVE", DataType::Boolean, true),
+            Field::new("DAY", DataType::Date32, true),
+            Field::new("AT", DataType::Timestamp(TimeUnit::Microsecond, None), true),
+            Field::new(
+                "ZONED",
+                DataType::Timestamp(TimeUnit::Microsecond, Some("+01:00".into())),
+                true,
+            ),
+            Field::new("DATA", DataType::Binary, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![Some(i64::MAX), None])),
+                Arc::new(StringArray::from(vec![Some("O'Brien \\ co"), None])),
+                Arc::new(
+                    Decimal128Array::from(vec![Some(1250), None])
+                        .with_precision_and_scale(10, 2)
+                        .unwrap(),
+                ),
+                Arc::new(Float64Array::from(vec![Some(0.5), Some(f64::NAN)])),
+                Arc::new(BooleanArray::from(vec![Some(true), None])),
+                Arc::new(Date32Array::from(vec![Some(19787), None])),
+                Arc::new(TimestampMicrosecondArray::from(vec![
+                    Some(1_709_634_600_000_000),
+                    None,
+                ])),
+                Arc::new(
+                    TimestampMicrosecondArray::from(vec![Some(1_709_634_600_250_000), None])
+                        .with_timezone("+01:00"),
+                ),
+                Arc::new(BinaryArray::from(vec![Some(&b"\x01\xab"[..]), None])),
+            ],
+        )
+        .unwrap();
+
+        let mut out = Vec::new();
+        write_inserts(
+            RecordBatchIterator::new([Ok(batch)], schema),
+            "DB.PUBLIC.T",
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "INSERT INTO DB.PUBLIC.T VALUES (9223372036854775807, 'O''Brien \\\\ co', 12.50, \
+             0.5, TRUE, DATE '2024-03-05', TIMESTAMP_NTZ '2024-03-05 10:30:00', \
+             TIMESTAMP_TZ '2024-03-05 11:30:00.250 +01:00', X'01ab');\n\
+             INSERT INTO DB.PUBLIC.T VALUES (NULL, NULL, NULL, 'NaN', NULL, NULL, NULL, NULL, \
+             NULL);\n"
+        );
+    }
+}
@@ -0,0 +1,99 @@
+//! A heartbeat run in the background while the interactive prompt is idle,
+//! so the next query after a long pause doesn't hit a cold or dropped
+//! connection path.
+
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use anyhow::Result;
+
+/// Runs a ping every interval until dropped, skipping it while a query holds
+/// [`Heartbeat::busy`].
+pub struct Heartbeat {
+    busy: Arc<Mutex<()>>,
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Heartbeat {
+    pub fn start(
+        interval: Duration,
+        mut ping: impl FnMut() -> Result<()> + Send + 'static,
+    ) -> Self {
+        let busy = Arc::new(Mutex::new(()));
+        let (stop, stopped) = mpsc::channel::<()>();
+        let thread = thread::spawn({
+            let busy = busy.clone();
+            move || {
+                let mut failing = false;
+                while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                    // A query is running, which keeps the session warm anyway.
+                    let Ok(_idle) = busy.try_lock() else {
+                        continue;
+                    };
+                    match ping() {
+                        Ok(()) => failing = false,
+                        // Warn once per outage rather than on every beat.
+                        Err(e) if !failing => {
+                            failing = true;
+                            eprintln!("\nWarning: keepalive failed: {:#}", e);
+                        }
+                        Err(_) => {}
+                    }
+                }
+            }
+        });
+        Self {
+            busy,
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+
+    /// Hold while running a query so the heartbeat doesn't interleave with it;
+    /// waits for an in-progress ping to finish.
+    pub fn busy(&self) -> MutexGuard<'_, ()> {
+        self.busy.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for Heartbeat {
+    fn drop(&mut self) {
+        // Closing the channel wakes and stops the thread.
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn pings_only_while_idle() {
+        let pings = Arc::new(AtomicUsize::new(0));
+        let heartbeat = Heartbeat::start(Duration::from_millis(5), {
+            let pings = pings.clone();
+            move || {
+                pings.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        });
+
+        let query = heartbeat.busy();
+        let before = pings.load(Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(pings.load(Ordering::SeqCst), before);
+        drop(query);
+
+        thread::sleep(Duration::from_millis(50));
+        assert!(pings.load(Ordering::SeqCst) > before);
+        drop(heartbeat);
+    }
+}
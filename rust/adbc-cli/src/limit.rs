@@ -0,0 +1,248 @@
+//! A guard on the number of rows fetched, so an accidental `SELECT *` of a
+//! huge table can't exhaust memory, a question before printing a large result
+//! to a terminal, and `--head`/`--tail` row selection.
+
+use std::collections::VecDeque;
+
+use arrow_array::{RecordBatch, RecordBatchReader};
+use arrow_schema::{ArrowError, SchemaRef};
+use arrow_select::concat::concat_batches;
+
+/// Wraps a reader and stops fetching once `max_rows` rows have been read,
+/// printing a warning. A `max_rows` of 0 disables the limit.
+pub struct MaxRows<R> {
+    inner: R,
+    max_rows: usize,
+    fetched: usize,
+    done: bool,
+    warn: bool,
+}
+
+impl<R: RecordBatchReader> MaxRows<R> {
+    pub fn new(inner: R, max_rows: usize) -> Self {
+        Self {
+            inner,
+            max_rows,
+            fetched: 0,
+            done: false,
+            warn: true,
+        }
+    }
+
+    /// Like [`MaxRows::new`], but for a deliberate `--head`: no warning is
+    /// printed when the limit is reached.
+    pub fn head(inner: R, rows: usize) -> Self {
+        Self {
+            warn: false,
+            ..Self::new(inner, rows)
+        }
+    }
+}
+
+impl<R: RecordBatchReader> Iterator for MaxRows<R> {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let batch = match self.inner.next()? {
+            Ok(batch) => batch,
+            Err(e) => return Some(Err(e)),
+        };
+        if self.max_rows == 0 {
+            return Some(Ok(batch));
+        }
+
+        let remaining = self.max_rows - self.fetched;
+        if batch.num_rows() <= remaining {
+            self.fetched += batch.num_rows();
+            return Some(Ok(batch));
+        }
+
+        self.done = true;
+        if self.warn {
+            eprintln!(
+                "Warning: stopped fetching after {} rows; pass --max-rows 0 to fetch all rows",
+                self.max_rows
+            );
+        }
+        Some(Ok(batch.slice(0, remaining)))
+    }
+}
+
+/// Drain `reader`, keeping only its last `rows` rows. At most `rows` rows and
+/// the batch being read are held in memory at once.
+pub fn tail(reader: impl RecordBatchReader, rows: usize) -> Result<Vec<RecordBatch>, ArrowError> {
+    let mut kept: VecDeque<RecordBatch> = VecDeque::new();
+    let mut kept_rows = 0;
+    for batch in reader {
+        let batch = batch?;
+        kept_rows += batch.num_rows();
+        kept.push_back(batch);
+
+        while let Some(front) = kept.front() {
+            let excess = kept_rows.saturating_sub(rows);
+            if excess == 0 {
+                break;
+            }
+            if front.num_rows() <= excess {
+                kept_rows -= front.num_rows();
+                kept.pop_front();
+            } else {
+                // Copy the rows still needed, so the rest of the batch's
+                // buffers can be freed.
+                let front = kept.pop_front().expect("front exists");
+                let needed = front.slice(excess, front.num_rows() - excess);
+                kept.push_front(concat_batches(&needed.schema(), [&needed])?);
+                kept_rows -= excess;
+            }
+        }
+    }
+    Ok(kept.into())
+}
+
+impl<R: RecordBatchReader> RecordBatchReader for MaxRows<R> {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+}
+
+/// A reader that returns batches already read from `inner` before the rest.
+pub struct Buffered<R> {
+    buffered: VecDeque<RecordBatch>,
+    inner: R,
+}
+
+impl<R: RecordBatchReader> Iterator for Buffered<R> {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.buffered.pop_front() {
+            Some(batch) => Some(Ok(batch)),
+            None => self.inner.next(),
+        }
+    }
+}
+
+impl<R: RecordBatchReader> RecordBatchReader for Buffered<R> {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+}
+
+/// Read batches of `reader` until it has more than `threshold` rows, and if
+/// it does, pass the number read to `ask` to decide whether to go on. Returns
+/// the whole result to print, or `None` if `ask` said no.
+pub fn confirm_rows<R: RecordBatchReader, E: From<ArrowError>>(
+    mut reader: R,
+    threshold: usize,
+    ask: impl FnOnce(usize) -> Result<bool, E>,
+) -> Result<Option<Buffered<R>>, E> {
+    let mut buffered = VecDeque::new();
+    let mut rows = 0;
+    while rows <= threshold {
+        let Some(batch) = reader.next().transpose()? else {
+            break;
+        };
+        rows += batch.num_rows();
+        buffered.push_back(batch);
+    }
+    if rows > threshold && !ask(rows)? {
+        return Ok(None);
+    }
+    Ok(Some(Buffered {
+        buffered,
+        inner: reader,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::Int64Type;
+    use arrow_array::{Int64Array, RecordBatchIterator};
+    use arrow_schema::{DataType, Field, Schema};
+
+    use super::*;
+
+    fn reader(batches: usize) -> impl RecordBatchReader {
+        let schema = Arc::new(Schema::new(vec![Field::new("N", DataType::Int64, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from(vec![1, 2, 3]))],
+        )
+        .unwrap();
+        RecordBatchIterator::new(vec![batch; batches].into_iter().map(Ok), schema)
+    }
+
+    fn values(batches: &[RecordBatch]) -> Vec<i64> {
+        batches
+            .iter()
+            .flat_map(|batch| {
+                batch
+                    .column(0)
+                    .as_primitive::<Int64Type>()
+                    .values()
+                    .to_vec()
+            })
+            .collect()
+    }
+
+    fn rows(reader: impl RecordBatchReader) -> usize {
+        reader.map(|batch| batch.unwrap().num_rows()).sum()
+    }
+
+    #[test]
+    fn stops_at_max_rows() {
+        assert_eq!(rows(MaxRows::new(reader(4), 7)), 7);
+        assert_eq!(rows(MaxRows::new(reader(4), 6)), 6);
+        assert_eq!(rows(MaxRows::new(reader(4), 0)), 12);
+    }
+
+    #[test]
+    fn head_and_tail() {
+        assert_eq!(rows(MaxRows::head(reader(4), 5)), 5);
+
+        // A million rows in 1,000-row batches, generated as they are read.
+        let schema = Arc::new(Schema::new(vec![Field::new("N", DataType::Int64, false)]));
+        let batches = (0..1000).map(|idx| {
+            let start = idx * 1000;
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(Int64Array::from_iter_values(start..start + 1000))],
+            )
+        });
+        let large = RecordBatchIterator::new(batches, schema.clone());
+        let last = tail(large, 1500).unwrap();
+        assert_eq!(values(&last), (998_500..1_000_000).collect::<Vec<_>>());
+        assert_eq!(last[0].num_rows(), 500);
+
+        assert_eq!(values(&tail(reader(2), 10).unwrap()), [1, 2, 3, 1, 2, 3]);
+        assert!(tail(reader(2), 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn confirm_large_results() {
+        let mut asked = None;
+        let small = confirm_rows(reader(3), 9, |rows| {
+            asked = Some(rows);
+            Ok::<_, ArrowError>(false)
+        });
+        assert_eq!(rows(small.unwrap().unwrap()), 9);
+        assert_eq!(asked, None);
+
+        // Only the batches up to the threshold are read before asking.
+        let large = confirm_rows(reader(100), 7, |rows| {
+            asked = Some(rows);
+            Ok::<_, ArrowError>(true)
+        });
+        assert_eq!(asked, Some(9));
+        assert_eq!(rows(large.unwrap().unwrap()), 300);
+
+        let declined = confirm_rows(reader(100), 7, |_| Ok::<_, ArrowError>(false));
+        assert!(declined.unwrap().is_none());
+    }
+}
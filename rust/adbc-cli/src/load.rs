@@ -0,0 +1,416 @@
+//! Loading local CSV and Parquet files into tables with ADBC bulk ingest.
+
+use std::fs::File;
+use std::io::{Seek, SeekFrom};
+use std::path::Path;
+use std::sync::Arc;
+
+use adbc_core::options::{IngestMode, OptionStatement};
+use adbc_core::{Connection as _, Optionable, Statement as _};
+use adbc_snowflake::Connection;
+use anyhow::{bail, Context, Result};
+use arrow_array::{RecordBatch, RecordBatchReader};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+/// How rows are written to the target table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoadMode {
+    /// Create the table; fail if it already exists.
+    Create,
+    /// Drop and recreate the table.
+    Replace,
+    /// Append to an existing table with a compatible schema.
+    Append,
+}
+
+impl From<LoadMode> for IngestMode {
+    fn from(mode: LoadMode) -> Self {
+        match mode {
+            LoadMode::Create => IngestMode::Create,
+            LoadMode::Replace => IngestMode::Replace,
+            LoadMode::Append => IngestMode::Append,
+        }
+    }
+}
+
+/// A possibly qualified table name: `[[catalog.]schema.]table`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TableName<'a> {
+    pub catalog: Option<&'a str>,
+    pub db_schema: Option<&'a str>,
+    pub table: &'a str,
+}
+
+impl<'a> TableName<'a> {
+    pub fn parse(name: &'a str) -> Result<Self> {
+        let parts: Vec<&str> = name.split('.').collect();
+        match parts.as_slice() {
+            [table] => Ok(Self {
+                catalog: None,
+                db_schema: None,
+                table,
+            }),
+            [db_schema, table] => Ok(Self {
+                catalog: None,
+                db_schema: Some(db_schema),
+                table,
+            }),
+            [catalog, db_schema, table] => Ok(Self {
+                catalog: Some(catalog),
+                db_schema: Some(db_schema),
+                table,
+            }),
+            _ => bail!("Invalid table name: {}", name),
+        }
+    }
+}
+
+/// Open a CSV or Parquet file, chosen by extension, as a stream of batches.
+/// CSV schemas are inferred from the file contents.
+pub fn open_file(path: &Path) -> Result<Box<dyn RecordBatchReader + Send>> {
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase);
+
+    match extension.as_deref() {
+        Some("csv") => {
+            let format = arrow_csv::reader::Format::default().with_header(true);
+            let (schema, _) = format
+                .infer_schema(&mut file, None)
+                .with_context(|| format!("Failed to infer schema of {}", path.display()))?;
+            file.seek(SeekFrom::Start(0))?;
+            let reader = arrow_csv::ReaderBuilder::new(Arc::new(schema))
+                .with_format(format)
+                .build(file)?;
+            Ok(Box::new(reader))
+        }
+        Some("parquet") => {
+            let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+                .with_context(|| format!("Failed to read Parquet file {}", path.display()))?
+                .build()?;
+            Ok(Box::new(reader))
+        }
+        _ => bail!(
+            "Unsupported file type: {} (expected .csv or .parquet)",
+            path.display()
+        ),
+    }
+}
+
+/// Broad type families used to decide whether a file column can be appended
+/// to an existing table column; Snowflake's Arrow types rarely match a file's
+/// types exactly (e.g. NUMBER arrives as Decimal128).
+fn type_family(data_type: &DataType) -> &'static str {
+    match data_type {
+        dt if dt.is_numeric() => "numeric",
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => "string",
+        DataType::Boolean => "boolean",
+        DataType::Binary | DataType::LargeBinary | DataType::BinaryView => "binary",
+        dt if dt.is_temporal() => "temporal",
+        _ => "other",
+    }
+}
+
+/// The decimal digits needed for every value of an integer type.
+fn integer_digits(data_type: &DataType) -> Option<u8> {
+    match data_type {
+        DataType::Int8 | DataType::UInt8 => Some(3),
+        DataType::Int16 | DataType::UInt16 => Some(5),
+        DataType::Int32 | DataType::UInt32 => Some(10),
+        DataType::Int64 => Some(19),
+        DataType::UInt64 => Some(20),
+        _ => None,
+    }
+}
+
+/// Whether every value of `from` converts to `to` without loss, e.g. Int32 to
+/// Int64 or to NUMBER(10, 0) and wider.
+fn is_widening(from: &DataType, to: &DataType) -> bool {
+    use DataType::*;
+    match (from, to) {
+        (Int8, Int16 | Int32 | Int64)
+        | (Int16, Int32 | Int64)
+        | (Int32, Int64)
+        | (UInt8, Int16 | Int32 | Int64 | UInt16 | UInt32 | UInt64)
+        | (UInt16, Int32 | Int64 | UInt32 | UInt64)
+        | (UInt32, Int64 | UInt64)
+        | (Int8 | Int16 | Int32 | UInt8 | UInt16 | UInt32 | Float32, Float64)
+        | (Utf8, LargeUtf8)
+        | (Binary, LargeBinary)
+        | (Date32, Date64) => true,
+        (Decimal128(from_precision, from_scale), Decimal128(precision, scale)) => {
+            scale >= from_scale
+                && i16::from(*precision) - i16::from(*scale)
+                    >= i16::from(*from_precision) - i16::from(*from_scale)
+        }
+        (from, Decimal128(precision, scale)) => integer_digits(from)
+            .is_some_and(|digits| i16::from(*precision) - i16::from(*scale) >= digits.into()),
+        _ => false,
+    }
+}
+
+/// Check that every column of `incoming` exists in `target` with a compatible
+/// type, and that every NOT NULL column of `target` is in `incoming`. Column
+/// names are compared case-insensitively, as Snowflake folds unquoted
+/// identifiers to upper case.
+///
+/// Without `cast`, a column is compatible if its type is in the same family
+/// as the table's, leaving the conversion to Snowflake. With `cast`, its type
+/// must match the table's or widen to it without loss, and the schema to cast
+/// the file to is returned if any column needs it.
+pub fn check_append_compatible(
+    incoming: &Schema,
+    target: &Schema,
+    cast: bool,
+) -> Result<Option<SchemaRef>> {
+    let find = |schema: &Schema, name: &str| {
+        schema
+            .fields()
+            .iter()
+            .find(|field| field.name().eq_ignore_ascii_case(name))
+            .cloned()
+    };
+    let mut problems = Vec::new();
+    let mut fields: Vec<Field> = Vec::with_capacity(incoming.fields().len());
+    let mut casts = false;
+    for field in incoming.fields() {
+        let mut field = field.as_ref().clone();
+        match find(target, field.name()) {
+            None => problems.push(format!(
+                "column {} does not exist in the table",
+                field.name()
+            )),
+            Some(target) if target.data_type() == field.data_type() => {}
+            Some(target) if cast && is_widening(field.data_type(), target.data_type()) => {
+                field = field.with_data_type(target.data_type().clone());
+                casts = true;
+            }
+            Some(target)
+                if cast || type_family(target.data_type()) != type_family(field.data_type()) =>
+            {
+                problems.push(format!(
+                    "column {} is {} in the file but {} in the table{}",
+                    field.name(),
+                    field.data_type(),
+                    target.data_type(),
+                    if cast {
+                        ", and can't be cast without loss"
+                    } else {
+                        ""
+                    }
+                ))
+            }
+            Some(_) => {}
+        }
+        fields.push(field);
+    }
+    for target in target.fields() {
+        if !target.is_nullable() && find(incoming, target.name()).is_none() {
+            problems.push(format!(
+                "column {} is NOT NULL in the table but missing from the file",
+                target.name()
+            ));
+        }
+    }
+
+    if !problems.is_empty() {
+        bail!(
+            "File schema is not compatible with the table:\n  {}",
+            problems.join("\n  ")
+        )
+    }
+    Ok(casts.then(|| {
+        Arc::new(Schema::new_with_metadata(
+            fields,
+            incoming.metadata().clone(),
+        ))
+    }))
+}
+
+/// Casts the columns of a reader's batches to the types of `schema`, for
+/// `--cast`.
+struct Cast {
+    inner: Box<dyn RecordBatchReader + Send>,
+    schema: SchemaRef,
+}
+
+impl Iterator for Cast {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let batch = match self.inner.next()? {
+            Ok(batch) => batch,
+            Err(e) => return Some(Err(e)),
+        };
+        let columns = batch
+            .columns()
+            .iter()
+            .zip(self.schema.fields())
+            .map(|(column, field)| arrow_cast::cast(column, field.data_type()))
+            .collect::<Result<Vec<_>, _>>();
+        Some(columns.and_then(|columns| RecordBatch::try_new(self.schema.clone(), columns)))
+    }
+}
+
+impl RecordBatchReader for Cast {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+/// Load `path` into `table`, returning the number of rows loaded if the
+/// driver reports it. With `cast`, appended columns are cast to the table's
+/// types where that loses nothing.
+pub fn load(
+    connection: &mut Connection,
+    path: &Path,
+    table: &str,
+    mode: LoadMode,
+    cast: bool,
+) -> Result<Option<i64>> {
+    let name = TableName::parse(table)?;
+    let mut reader = open_file(path)?;
+
+    if mode == LoadMode::Append {
+        let target = connection
+            .get_table_schema(name.catalog, name.db_schema, name.table)
+            .with_context(|| format!("Failed to get schema of table {}", table))?;
+        if let Some(schema) = check_append_compatible(&reader.schema(), &target, cast)? {
+            reader = Box::new(Cast {
+                inner: reader,
+                schema,
+            });
+        }
+    }
+
+    let mut statement = connection
+        .new_statement()
+        .context("Failed to create statement")?;
+    statement.set_option(OptionStatement::TargetTable, name.table.into())?;
+    if let Some(catalog) = name.catalog {
+        statement.set_option(OptionStatement::TargetCatalog, catalog.into())?;
+    }
+    if let Some(db_schema) = name.db_schema {
+        statement.set_option(OptionStatement::TargetDbSchema, db_schema.into())?;
+    }
+    statement.set_option(OptionStatement::IngestMode, IngestMode::from(mode).into())?;
+    statement
+        .bind_stream(reader)
+        .context("Failed to bind file contents")?;
+
+    statement
+        .execute_update()
+        .with_context(|| format!("Failed to load {} into {}", path.display(), table))
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_schema::Field;
+
+    use super::*;
+
+    #[test]
+    fn parse_table_names() {
+        assert_eq!(
+            TableName::parse("DB.PUBLIC.T").unwrap(),
+            TableName {
+                catalog: Some("DB"),
+                db_schema: Some("PUBLIC"),
+                table: "T"
+            }
+        );
+        assert_eq!(TableName::parse("T").unwrap().db_schema, None);
+        assert!(TableName::parse("a.b.c.d").is_err());
+    }
+
+    #[test]
+    fn append_compatibility() {
+        let target = Schema::new(vec![
+            Field::new("ID", DataType::Decimal128(38, 0), false),
+            Field::new("NAME", DataType::Utf8, true),
+        ]);
+
+        let ok = Schema::new(vec![
+            Field::new("id", DataType::Int64, true),
+            Field::new("name", DataType::Utf8, true),
+        ]);
+        assert!(check_append_compatible(&ok, &target, false)
+            .unwrap()
+            .is_none());
+
+        let bad = Schema::new(vec![
+            Field::new("id", DataType::Utf8, true),
+            Field::new("extra", DataType::Utf8, true),
+        ]);
+        let message = check_append_compatible(&bad, &target, false)
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("column id is Utf8 in the file"));
+        assert!(message.contains("column extra does not exist"));
+
+        let missing = Schema::new(vec![Field::new("name", DataType::Utf8, true)]);
+        let message = check_append_compatible(&missing, &target, false)
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("column ID is NOT NULL in the table but missing"));
+    }
+
+    #[test]
+    fn cast_widening_columns() {
+        let target = Schema::new(vec![
+            Field::new("ID", DataType::Decimal128(38, 0), false),
+            Field::new("PRICE", DataType::Decimal128(10, 2), true),
+            Field::new("NAME", DataType::LargeUtf8, true),
+        ]);
+        let incoming = Schema::new(vec![
+            Field::new("id", DataType::Int64, true),
+            Field::new("name", DataType::Utf8, true),
+        ]);
+        let schema = check_append_compatible(&incoming, &target, true)
+            .unwrap()
+            .unwrap();
+        assert_eq!(schema.field(0).name(), "id");
+        assert_eq!(schema.field(0).data_type(), &DataType::Decimal128(38, 0));
+        assert_eq!(schema.field(1).data_type(), &DataType::LargeUtf8);
+
+        let ids = arrow_array::Int64Array::from(vec![1, 2]);
+        let names = arrow_array::StringArray::from(vec!["a", "b"]);
+        let batch = RecordBatch::try_new(
+            Arc::new(incoming.clone()),
+            vec![Arc::new(ids), Arc::new(names)],
+        )
+        .unwrap();
+        let reader = Cast {
+            inner: Box::new(arrow_array::RecordBatchIterator::new(
+                [Ok(batch)],
+                Arc::new(incoming),
+            )),
+            schema: schema.clone(),
+        };
+        let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(batches[0].schema(), schema);
+
+        // A float can't become NUMBER(10, 2) without rounding.
+        let lossy = Schema::new(vec![
+            Field::new("ID", DataType::Int32, false),
+            Field::new("PRICE", DataType::Float64, true),
+        ]);
+        assert!(check_append_compatible(&lossy, &target, false).is_ok());
+        let message = check_append_compatible(&lossy, &target, true)
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains(
+            "column PRICE is Float64 in the file but Decimal128(10, 2) in the table, \
+             and can't be cast without loss"
+        ));
+        assert!(!is_widening(&DataType::Int64, &DataType::Decimal128(18, 0)));
+        assert!(is_widening(
+            &DataType::Decimal128(5, 2),
+            &DataType::Decimal128(10, 4)
+        ));
+    }
+}
@@ -0,0 +1,182 @@
+//! Locale conventions for numbers and dates in table output, for `--locale`.
+//!
+//! A small built-in table covers the decimal separator, the digit grouping
+//! separator, and the order of dates for common locales; machine formats
+//! (CSV, JSON, Arrow, Parquet) never use it.
+
+use std::str::FromStr;
+
+/// The order of the year, month, and day of a date.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DateOrder {
+    /// 2024-03-05, the ISO 8601 order.
+    Ymd,
+    /// 05.03.2024
+    Dmy,
+    /// 03/05/2024
+    Mdy,
+}
+
+/// How numbers and dates are written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Locale {
+    decimal: char,
+    group: char,
+    date_order: DateOrder,
+    date_separator: char,
+}
+
+impl Default for Locale {
+    /// Arrow's own conventions: `.` decimals, `,` grouping, and ISO dates.
+    fn default() -> Self {
+        Locale::new('.', ',', DateOrder::Ymd, '-')
+    }
+}
+
+/// Supported locales by language and region; a bare language, such as `de`,
+/// uses its first entry.
+const LOCALES: &[(&str, Locale)] = &[
+    ("en-us", Locale::new('.', ',', DateOrder::Mdy, '/')),
+    ("en-gb", Locale::new('.', ',', DateOrder::Dmy, '/')),
+    ("en-ca", Locale::new('.', ',', DateOrder::Ymd, '-')),
+    ("en-au", Locale::new('.', ',', DateOrder::Dmy, '/')),
+    ("en-in", Locale::new('.', ',', DateOrder::Dmy, '/')),
+    ("de-de", Locale::new(',', '.', DateOrder::Dmy, '.')),
+    ("de-at", Locale::new(',', ' ', DateOrder::Dmy, '.')),
+    ("de-ch", Locale::new('.', '\'', DateOrder::Dmy, '.')),
+    ("fr-fr", Locale::new(',', ' ', DateOrder::Dmy, '/')),
+    ("fr-ca", Locale::new(',', ' ', DateOrder::Ymd, '-')),
+    ("es-es", Locale::new(',', '.', DateOrder::Dmy, '/')),
+    ("es-mx", Locale::new('.', ',', DateOrder::Dmy, '/')),
+    ("it-it", Locale::new(',', '.', DateOrder::Dmy, '/')),
+    ("pt-br", Locale::new(',', '.', DateOrder::Dmy, '/')),
+    ("pt-pt", Locale::new(',', ' ', DateOrder::Dmy, '/')),
+    ("nl-nl", Locale::new(',', '.', DateOrder::Dmy, '-')),
+    ("sv-se", Locale::new(',', ' ', DateOrder::Ymd, '-')),
+    ("pl-pl", Locale::new(',', ' ', DateOrder::Dmy, '.')),
+    ("ru-ru", Locale::new(',', ' ', DateOrder::Dmy, '.')),
+    ("ja-jp", Locale::new('.', ',', DateOrder::Ymd, '/')),
+    ("zh-cn", Locale::new('.', ',', DateOrder::Ymd, '-')),
+    ("ko-kr", Locale::new('.', ',', DateOrder::Ymd, '.')),
+];
+
+impl FromStr for Locale {
+    type Err = String;
+
+    /// Parse a locale name such as `de-DE`, `de_DE.UTF-8`, or `de`; `C`,
+    /// `POSIX`, and `ISO` select the default.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s
+            .split(['.', '@'])
+            .next()
+            .unwrap_or_default()
+            .replace('_', "-")
+            .to_ascii_lowercase();
+        if matches!(name.as_str(), "c" | "posix" | "iso") {
+            return Ok(Locale::default());
+        }
+        LOCALES
+            .iter()
+            .find(|(known, _)| {
+                *known == name || (!name.contains('-') && known.split('-').next() == Some(&name))
+            })
+            .map(|(_, locale)| *locale)
+            .ok_or_else(|| {
+                let known: Vec<&str> = LOCALES.iter().map(|(name, _)| *name).collect();
+                format!("unknown locale '{}'; supported: C, {}", s, known.join(", "))
+            })
+    }
+}
+
+impl Locale {
+    const fn new(decimal: char, group: char, date_order: DateOrder, date_separator: char) -> Self {
+        Locale {
+            decimal,
+            group,
+            date_order,
+            date_separator,
+        }
+    }
+
+    /// Rewrite a number as Rust displays it (e.g. `-1234567.25`) with this
+    /// locale's decimal separator and, if `grouped`, its separator between
+    /// each group of three digits of the integer part.
+    pub fn number(&self, number: &str, grouped: bool) -> String {
+        let (sign, unsigned) = match number.strip_prefix('-') {
+            Some(unsigned) => ("-", unsigned),
+            None => ("", number),
+        };
+        let digits = unsigned
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(unsigned.len());
+        let (integer, rest) = unsigned.split_at(digits);
+        let mut localized = String::with_capacity(number.len() + integer.len() / 3);
+        localized.push_str(sign);
+        for (idx, digit) in integer.chars().enumerate() {
+            if grouped && idx > 0 && (integer.len() - idx) % 3 == 0 {
+                localized.push(self.group);
+            }
+            localized.push(digit);
+        }
+        match rest.strip_prefix('.') {
+            Some(fraction) => {
+                localized.push(self.decimal);
+                localized.push_str(fraction);
+            }
+            None => localized.push_str(rest),
+        }
+        localized
+    }
+
+    /// Rewrite the leading `YYYY-MM-DD` of an ISO 8601 date or timestamp in
+    /// this locale's date order, keeping the rest (e.g. the time) as it is.
+    pub fn date(&self, iso: &str) -> String {
+        let parts = iso.get(..10).and_then(|date| {
+            let mut parts = date.split('-');
+            let (y, m, d) = (parts.next()?, parts.next()?, parts.next()?);
+            (y.len() == 4 && m.len() == 2 && d.len() == 2).then_some((y, m, d))
+        });
+        let Some((y, m, d)) = parts else {
+            return iso.to_string();
+        };
+        let sep = self.date_separator;
+        let date = match self.date_order {
+            DateOrder::Ymd => format!("{}{}{}{}{}", y, sep, m, sep, d),
+            DateOrder::Dmy => format!("{}{}{}{}{}", d, sep, m, sep, y),
+            DateOrder::Mdy => format!("{}{}{}{}{}", m, sep, d, sep, y),
+        };
+        // Separate the time with a space rather than ISO's `T`.
+        let rest = &iso[10..];
+        match rest.strip_prefix('T') {
+            Some(time) => format!("{} {}", date, time),
+            None => format!("{}{}", date, rest),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn localize_numbers_and_dates() {
+        let de: Locale = "de_DE.UTF-8".parse().unwrap();
+        assert_eq!(de.number("-1234567.25", true), "-1.234.567,25");
+        assert_eq!(de.number("1234.5", false), "1234,5");
+        assert_eq!(de.date("2024-03-05"), "05.03.2024");
+
+        let us: Locale = "en-US".parse().unwrap();
+        assert_eq!(us.date("2024-03-05T10:30:00"), "03/05/2024 10:30:00");
+        assert_eq!(
+            "fr".parse::<Locale>().unwrap().number("1234567", true),
+            "1 234 567"
+        );
+
+        let iso = Locale::default();
+        assert_eq!("C".parse::<Locale>().unwrap(), iso);
+        assert_eq!(iso.number("1234567.5", true), "1,234,567.5");
+        assert_eq!(iso.number("NaN", true), "NaN");
+        assert_eq!(iso.date("2024-03-05T10:30:00Z"), "2024-03-05 10:30:00Z");
+        assert!("xx-YY".parse::<Locale>().is_err());
+    }
+}
@@ -0,0 +1,165 @@
+//! Choosing how Snowflake NUMBER columns arrive as Arrow types, for
+//! `--number-mapping`.
+//!
+//! The driver returns NUMBER as Decimal128 by default. With its high-precision
+//! option off, it returns NUMBER(p, 0) as Int64 and NUMBER(p, s) as Float64,
+//! which is what `int64` asks for. The driver has no mode that returns every
+//! NUMBER as a float, so `f64` also converts the Int64 columns client-side;
+//! every Snowflake integer type is an alias of NUMBER(38, 0), so those are the
+//! only Int64 columns a result can have.
+
+use std::sync::Arc;
+
+use adbc_core::options::{OptionStatement, OptionValue};
+use arrow_array::{RecordBatch, RecordBatchReader};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+use clap::ValueEnum;
+
+/// The driver's option controlling whether NUMBER columns arrive as
+/// Decimal128.
+pub const USE_HIGH_PRECISION: &str = "adbc.snowflake.sql.client_option.use_high_precision";
+
+/// How NUMBER columns arrive as Arrow types.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum NumberMapping {
+    /// NUMBER(p, 0) as Int64 and other NUMBER columns as Float64.
+    Int64,
+    /// Every NUMBER as Decimal128, keeping its precision and scale.
+    Decimal,
+    /// Every NUMBER as Float64.
+    F64,
+}
+
+impl NumberMapping {
+    /// The statement option that asks the driver for this mapping.
+    pub fn statement_option(self) -> (OptionStatement, OptionValue) {
+        let enabled = match self {
+            NumberMapping::Decimal => "true",
+            NumberMapping::Int64 | NumberMapping::F64 => "false",
+        };
+        (
+            OptionStatement::Other(USE_HIGH_PRECISION.to_string()),
+            OptionValue::String(enabled.to_string()),
+        )
+    }
+}
+
+fn is_fixed_point(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Int64 | DataType::Decimal128(_, _) | DataType::Decimal256(_, _)
+    )
+}
+
+/// `schema` with its NUMBER columns as Float64, for `f64`.
+pub fn float_schema(schema: &Schema) -> Schema {
+    let fields: Vec<Field> = schema
+        .fields()
+        .iter()
+        .map(|field| {
+            if is_fixed_point(field.data_type()) {
+                field.as_ref().clone().with_data_type(DataType::Float64)
+            } else {
+                field.as_ref().clone()
+            }
+        })
+        .collect();
+    Schema::new_with_metadata(fields, schema.metadata().clone())
+}
+
+/// Casts the NUMBER columns of a reader's batches to Float64, for `f64`.
+pub struct ToFloat<R> {
+    inner: R,
+    schema: SchemaRef,
+}
+
+impl<R: RecordBatchReader> ToFloat<R> {
+    pub fn new(inner: R) -> Self {
+        let schema = Arc::new(float_schema(&inner.schema()));
+        Self { inner, schema }
+    }
+
+    fn convert(&self, batch: RecordBatch) -> Result<RecordBatch, ArrowError> {
+        let columns = batch
+            .columns()
+            .iter()
+            .map(|column| {
+                if is_fixed_point(column.data_type()) {
+                    arrow_cast::cast(column, &DataType::Float64)
+                } else {
+                    Ok(column.clone())
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        RecordBatch::try_new(self.schema.clone(), columns)
+    }
+}
+
+impl<R: RecordBatchReader> Iterator for ToFloat<R> {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|batch| batch.and_then(|batch| self.convert(batch)))
+    }
+}
+
+impl<R: RecordBatchReader> RecordBatchReader for ToFloat<R> {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::Float64Type;
+    use arrow_array::{Array, Decimal128Array, Int64Array, RecordBatchIterator, StringArray};
+
+    use super::*;
+
+    #[test]
+    fn convert_numbers_to_float() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("ID", DataType::Int64, false),
+            Field::new("PRICE", DataType::Decimal128(10, 2), true),
+            Field::new("NAME", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2])),
+                Arc::new(
+                    Decimal128Array::from(vec![Some(1250), None])
+                        .with_precision_and_scale(10, 2)
+                        .unwrap(),
+                ),
+                Arc::new(StringArray::from(vec!["a", "b"])),
+            ],
+        )
+        .unwrap();
+        let reader = ToFloat::new(RecordBatchIterator::new([Ok(batch)], schema));
+        let schema = reader.schema();
+        assert_eq!(schema.field(0).data_type(), &DataType::Float64);
+        assert_eq!(schema.field(1).data_type(), &DataType::Float64);
+        assert_eq!(schema.field(2).data_type(), &DataType::Utf8);
+
+        let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+        let prices = batches[0].column(1).as_primitive::<Float64Type>();
+        assert_eq!(prices.value(0), 12.5);
+        assert!(prices.is_null(1));
+        assert_eq!(
+            batches[0]
+                .column(0)
+                .as_primitive::<Float64Type>()
+                .values()
+                .to_vec(),
+            [1.0, 2.0]
+        );
+
+        let (key, value) = NumberMapping::Decimal.statement_option();
+        assert_eq!(key.as_ref(), USE_HIGH_PRECISION);
+        assert!(matches!(value, OptionValue::String(v) if v == "true"));
+    }
+}
@@ -0,0 +1,390 @@
+//! Fetching and presenting the results of `SHOW`, `LIST`, and `DESCRIBE`
+//! commands.
+//!
+//! Snowflake returns at most 10,000 rows from a `SHOW` command. When a result
+//! hits that cap, the remaining rows are fetched page by page with
+//! `LIMIT <n> FROM '<name>'`, which resumes after the objects with that name,
+//! until the caller's row limit is passed. `LIST` and `DESCRIBE` results aren't
+//! capped, so they are only rearranged.
+
+use std::sync::Arc;
+
+use adbc_core::{Connection as _, Statement as _};
+use adbc_snowflake::Connection;
+use anyhow::{Context, Result};
+use arrow_array::cast::AsArray;
+use arrow_array::{RecordBatch, RecordBatchReader};
+use arrow_schema::{ArrowError, DataType, SchemaRef};
+
+use crate::sql;
+
+/// The most rows Snowflake returns from one `SHOW` command.
+pub const SHOW_PAGE_SIZE: usize = 10_000;
+
+/// Columns shown first, in this order, when present in a `SHOW` result.
+const LEADING_COLUMNS: &[&str] = &[
+    "name",
+    "database_name",
+    "schema_name",
+    "kind",
+    "owner",
+    "comment",
+];
+
+/// Returns true if `sql` lists objects or describes one, so its identifying
+/// columns are shown first.
+pub fn is_metadata_command(sql: &str) -> bool {
+    matches!(
+        sql::first_keyword(sql).as_deref(),
+        Some("SHOW" | "LIST" | "LS" | "DESC" | "DESCRIBE")
+    )
+}
+
+/// Returns true if `sql` is a `SHOW` command that can be paginated, i.e. one
+/// without its own `LIMIT`.
+pub fn is_paginated_show(sql: &str) -> bool {
+    sql::first_keyword(sql).as_deref() == Some("SHOW")
+        && !sql
+            .split_whitespace()
+            .any(|word| word.eq_ignore_ascii_case("LIMIT"))
+}
+
+/// The `SHOW` command fetching up to `rows` rows after the object named
+/// `last_name`.
+pub fn next_page_sql(sql: &str, rows: usize, last_name: &str) -> String {
+    format!(
+        "{} LIMIT {} FROM '{}'",
+        sql.trim().trim_end_matches(';'),
+        rows,
+        last_name.replace('\'', "''")
+    )
+}
+
+/// The rows to request in the next page, given the `fetched` so far, or None
+/// once more than `limit` rows were fetched. One row past the limit is enough
+/// for the caller to tell that the result was cut short.
+fn next_page_rows(fetched: usize, limit: Option<usize>) -> Option<usize> {
+    match limit {
+        Some(limit) if fetched > limit => None,
+        Some(limit) => Some((limit + 1 - fetched).min(SHOW_PAGE_SIZE)),
+        None => Some(SHOW_PAGE_SIZE),
+    }
+}
+
+/// Returns the `name` of each row in `batch`, if it has a string `name`
+/// column.
+fn names(batch: &RecordBatch) -> Option<Vec<&str>> {
+    let column = batch.column_by_name("name")?;
+    match column.data_type() {
+        DataType::Utf8 => Some(
+            column
+                .as_string::<i32>()
+                .iter()
+                .map(Option::unwrap_or_default)
+                .collect(),
+        ),
+        DataType::LargeUtf8 => Some(
+            column
+                .as_string::<i64>()
+                .iter()
+                .map(Option::unwrap_or_default)
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// The name to resume a `SHOW` command after, given a full `page`. Names
+/// repeat across databases and schemas, and `FROM '<name>'` skips every
+/// object with that name, so the rows sharing the last row's name are
+/// dropped from the page and fetched again with the next one, resuming after
+/// the name before them. A page of nothing but one name can't be split, so
+/// it resumes after that name.
+fn resume_point(page: &mut Vec<RecordBatch>) -> Option<String> {
+    let mut last = None;
+    for idx in (0..page.len()).rev() {
+        let names = names(&page[idx])?;
+        for (row, name) in names.iter().enumerate().rev() {
+            match &last {
+                None => last = Some(name.to_string()),
+                Some(last) if last == name => {}
+                Some(_) => {
+                    let name = name.to_string();
+                    page[idx] = page[idx].slice(0, row + 1);
+                    page.truncate(idx + 1);
+                    return Some(name);
+                }
+            }
+        }
+    }
+    last
+}
+
+fn execute(connection: &mut Connection, sql: &str) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+    let mut statement = connection
+        .new_statement()
+        .context("Failed to create statement")?;
+    statement
+        .set_sql_query(sql)
+        .context("Failed to set SQL query")?;
+    let reader = statement.execute().context("Failed to execute query")?;
+    let schema = reader.schema();
+    let batches = reader.collect::<Result<Vec<_>, _>>()?;
+    Ok((schema, batches))
+}
+
+/// Run a `SHOW` command, following up with further pages while each page is
+/// full, and return its rows: all of them, or with a `limit`, at least one
+/// more than that if the result has more.
+pub fn fetch_pages(
+    connection: &mut Connection,
+    sql: &str,
+    limit: Option<usize>,
+) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+    let (schema, first) = execute(connection, sql)?;
+    let batches = paginate(sql, first, limit, |page_sql| {
+        execute(connection, page_sql)
+            .map(|(_, page)| page)
+            .context("Failed to fetch the next page of SHOW results")
+    })?;
+    Ok((schema, batches))
+}
+
+/// The paging behind [`fetch_pages`], starting from the `first` page and
+/// fetching the others with `fetch`.
+fn paginate(
+    sql: &str,
+    first: Vec<RecordBatch>,
+    limit: Option<usize>,
+    mut fetch: impl FnMut(&str) -> Result<Vec<RecordBatch>>,
+) -> Result<Vec<RecordBatch>> {
+    let mut batches = Vec::new();
+    let mut fetched = 0;
+    let mut page = first;
+    let mut requested = SHOW_PAGE_SIZE;
+
+    loop {
+        let page_rows: usize = page.iter().map(RecordBatch::num_rows).sum();
+        if page_rows < requested || next_page_rows(fetched + page_rows, limit).is_none() {
+            batches.extend(page);
+            return Ok(batches);
+        }
+        let Some(name) = resume_point(&mut page) else {
+            batches.extend(page);
+            return Ok(batches);
+        };
+        fetched += page.iter().map(RecordBatch::num_rows).sum::<usize>();
+        batches.extend(page);
+        let Some(rows) = next_page_rows(fetched, limit) else {
+            return Ok(batches);
+        };
+        page = fetch(&next_page_sql(sql, rows, &name))?;
+        requested = rows;
+    }
+}
+
+/// The column order putting the identifying columns of `schema` first.
+fn leading_order(schema: &SchemaRef) -> Vec<usize> {
+    let leading = LEADING_COLUMNS
+        .iter()
+        .filter_map(|name| schema.index_of(name).ok());
+    let rest = (0..schema.fields().len())
+        .filter(|idx| !LEADING_COLUMNS.contains(&schema.field(*idx).name().as_str()));
+    leading.chain(rest).collect()
+}
+
+/// Move the identifying columns of a metadata result (name, database,
+/// schema, kind, owner, comment) to the front, keeping the rest in their
+/// original order.
+pub fn reorder_columns(
+    schema: &SchemaRef,
+    batches: Vec<RecordBatch>,
+) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+    let indices = leading_order(schema);
+    let reordered = Arc::new(schema.project(&indices)?);
+    let batches = batches
+        .iter()
+        .map(|batch| batch.project(&indices))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((reordered, batches))
+}
+
+/// Wraps a reader, moving the identifying columns of each batch to the front
+/// as [`reorder_columns`] does, without collecting the result.
+pub struct LeadingColumns<R> {
+    inner: R,
+    indices: Vec<usize>,
+    schema: SchemaRef,
+}
+
+impl<R: RecordBatchReader> LeadingColumns<R> {
+    pub fn new(inner: R) -> Result<Self> {
+        let schema = inner.schema();
+        let indices = leading_order(&schema);
+        Ok(Self {
+            schema: Arc::new(schema.project(&indices)?),
+            inner,
+            indices,
+        })
+    }
+}
+
+impl<R: RecordBatchReader> Iterator for LeadingColumns<R> {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(
+            self.inner
+                .next()?
+                .and_then(|batch| batch.project(&self.indices)),
+        )
+    }
+}
+
+impl<R: RecordBatchReader> RecordBatchReader for LeadingColumns<R> {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{Int64Array, StringArray};
+    use arrow_schema::{Field, Schema};
+
+    use super::*;
+
+    #[test]
+    fn paginated_show_commands() {
+        assert!(is_paginated_show("show tables in schema db.public"));
+        assert!(is_paginated_show("SHOW TABLES LIKE '%limit%'"));
+        assert!(!is_paginated_show("SHOW TABLES LIMIT 10"));
+        assert!(!is_paginated_show("SELECT * FROM t"));
+        assert!(!is_paginated_show("LIST @stage"));
+        assert!(is_metadata_command("list @stage"));
+        assert!(is_metadata_command("DESC TABLE t"));
+        assert!(!is_metadata_command("SELECT * FROM t"));
+        assert_eq!(
+            next_page_sql("SHOW TABLES;", 10_000, "O'BRIEN"),
+            "SHOW TABLES LIMIT 10000 FROM 'O''BRIEN'"
+        );
+    }
+
+    #[test]
+    fn stop_paging_past_the_limit() {
+        assert_eq!(next_page_rows(10_000, None), Some(10_000));
+        assert_eq!(next_page_rows(10_000, Some(1_000_000)), Some(10_000));
+        assert_eq!(next_page_rows(10_000, Some(15_000)), Some(5_001));
+        assert_eq!(next_page_rows(10_000, Some(10_000)), Some(1));
+        assert_eq!(next_page_rows(10_001, Some(10_000)), None);
+    }
+
+    /// A `SHOW` result of the objects named `names`, in order, with each
+    /// object's schema.
+    fn show(names: &[(&str, &str)]) -> Vec<RecordBatch> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("name", DataType::Utf8, true),
+            Field::new("schema_name", DataType::Utf8, true),
+        ]));
+        let (names, schemas): (Vec<&str>, Vec<&str>) = names.iter().copied().unzip();
+        vec![RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(StringArray::from(names)),
+                Arc::new(StringArray::from(schemas)),
+            ],
+        )
+        .unwrap()]
+    }
+
+    #[test]
+    fn page_through_names_repeated_across_schemas() {
+        // A full first page ending partway through three tables named ORDERS.
+        let mut objects: Vec<(String, &str)> = (0..SHOW_PAGE_SIZE - 2)
+            .map(|i| (format!("A{:05}", i), "PUBLIC"))
+            .collect();
+        for schema in ["RAW", "STAGING", "PUBLIC"] {
+            objects.push(("ORDERS".to_string(), schema));
+        }
+        objects.push(("ZONES".to_string(), "PUBLIC"));
+        let objects: Vec<(&str, &str)> = objects
+            .iter()
+            .map(|(name, schema)| (name.as_str(), *schema))
+            .collect();
+
+        let mut queries = Vec::new();
+        let batches = paginate(
+            "SHOW TABLES",
+            show(&objects[..SHOW_PAGE_SIZE]),
+            None,
+            |sql| {
+                queries.push(sql.to_string());
+                let after = sql.rsplit_once("FROM '").unwrap().1.trim_end_matches('\'');
+                Ok(show(
+                    &objects
+                        .iter()
+                        .copied()
+                        .filter(|(name, _)| *name > after)
+                        .collect::<Vec<_>>(),
+                ))
+            },
+        )
+        .unwrap();
+
+        assert_eq!(queries, ["SHOW TABLES LIMIT 10000 FROM 'A09997'"]);
+        let fetched: Vec<(&str, &str)> = batches
+            .iter()
+            .flat_map(|batch| {
+                let schemas = batch.column(1).as_string::<i32>();
+                names(batch)
+                    .unwrap()
+                    .into_iter()
+                    .zip(schemas.iter().map(Option::unwrap))
+            })
+            .collect();
+        assert_eq!(fetched, objects);
+
+        // A page of one name can only resume after it.
+        let mut same = show(&[("ORDERS", "RAW"), ("ORDERS", "PUBLIC")]);
+        assert_eq!(resume_point(&mut same).as_deref(), Some("ORDERS"));
+        assert_eq!(same[0].num_rows(), 2);
+    }
+
+    #[test]
+    fn reorder_show_columns() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("created_on", DataType::Utf8, true),
+            Field::new("rows", DataType::Int64, true),
+            Field::new("name", DataType::Utf8, true),
+            Field::new("kind", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec!["2024-01-01"])),
+                Arc::new(Int64Array::from(vec![5])),
+                Arc::new(StringArray::from(vec!["T"])),
+                Arc::new(StringArray::from(vec!["TABLE"])),
+            ],
+        )
+        .unwrap();
+        assert_eq!(resume_point(&mut vec![batch.clone()]).as_deref(), Some("T"));
+
+        let (reordered, batches) = reorder_columns(&schema, vec![batch]).unwrap();
+        let names: Vec<&str> = reordered
+            .fields()
+            .iter()
+            .map(|f| f.name().as_str())
+            .collect();
+        assert_eq!(names, ["name", "kind", "created_on", "rows"]);
+        assert_eq!(batches[0].schema(), reordered);
+
+        let reader = arrow_array::RecordBatchIterator::new(batches.into_iter().map(Ok), reordered);
+        let mut streamed = LeadingColumns::new(reader).unwrap();
+        assert_eq!(streamed.schema().field(0).name(), "name");
+        assert_eq!(streamed.next().unwrap().unwrap().num_columns(), 4);
+
+        let empty = Arc::new(Schema::empty());
+        assert!(reorder_columns(&empty, Vec::new()).unwrap().1.is_empty());
+    }
+}
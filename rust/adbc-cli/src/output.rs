@@ -0,0 +1,1649 @@
+//! Rendering of query results.
+
+use std::fmt;
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use arrow_array::{
+    ArrayRef, BooleanArray, GenericStringArray, OffsetSizeTrait, RecordBatch, RecordBatchIterator,
+    RecordBatchReader, StringArray,
+};
+use arrow_cast::display::{ArrayFormatter, FormatOptions};
+use arrow_ipc::writer::StreamWriter;
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+use clap::ValueEnum;
+
+use crate::geo::{self, GeoFormat};
+use crate::insert;
+use crate::locale::Locale;
+use crate::size::ByteSize;
+
+/// The format used to render query results.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Aligned text table.
+    #[default]
+    Table,
+    /// Comma-separated values with a header row.
+    Csv,
+    /// Arrow IPC stream, for piping into Arrow-aware tools without loss.
+    ArrowStream,
+    /// A JSON array of row objects.
+    Json,
+    /// Newline-delimited JSON, one row object per line.
+    Ndjson,
+    /// Arrow IPC file (Feather v2), readable by DuckDB, Polars, and pandas.
+    ArrowFile,
+    /// Parquet file.
+    Parquet,
+    /// SQL INSERT statements, one per row, into the `--insert-table` table.
+    Insert,
+}
+
+impl OutputFormat {
+    /// The file extension used when writing this format to a file.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Table => "txt",
+            Self::Csv => "csv",
+            Self::ArrowStream => "arrows",
+            Self::Json => "json",
+            Self::Ndjson => "ndjson",
+            Self::ArrowFile => "arrow",
+            Self::Parquet => "parquet",
+            Self::Insert => "sql",
+        }
+    }
+
+    /// Whether this format is binary, so nothing else may be written to the
+    /// same stream.
+    pub fn is_binary(self) -> bool {
+        matches!(self, Self::ArrowStream | Self::ArrowFile | Self::Parquet)
+    }
+
+    /// The format conventionally stored in files with `path`'s extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "txt" => Some(Self::Table),
+            "csv" => Some(Self::Csv),
+            "arrows" => Some(Self::ArrowStream),
+            "json" => Some(Self::Json),
+            "ndjson" | "jsonl" => Some(Self::Ndjson),
+            "arrow" | "feather" | "ipc" => Some(Self::ArrowFile),
+            "parquet" => Some(Self::Parquet),
+            "sql" => Some(Self::Insert),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    /// The name `--output` takes, e.g. `arrow-stream`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self.to_possible_value().expect("no skipped formats");
+        f.write_str(value.get_name())
+    }
+}
+
+/// The compression codec used for Parquet output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ParquetCompression {
+    /// Zstandard: smaller files, still fast to decode.
+    #[default]
+    Zstd,
+    /// Snappy: the fastest to decode.
+    Snappy,
+    /// Uncompressed.
+    None,
+}
+
+impl ParquetCompression {
+    fn codec(self) -> parquet::basic::Compression {
+        match self {
+            Self::Zstd => parquet::basic::Compression::ZSTD(Default::default()),
+            Self::Snappy => parquet::basic::Compression::SNAPPY,
+            Self::None => parquet::basic::Compression::UNCOMPRESSED,
+        }
+    }
+}
+
+/// The characters used to draw text table borders.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum BorderStyle {
+    /// `|`, `-`, and `+` separators.
+    #[default]
+    Ascii,
+    /// Box-drawing characters.
+    Unicode,
+    /// Columns separated by whitespace only.
+    None,
+    /// A GitHub-flavored markdown table.
+    Markdown,
+}
+
+/// How numbers are written in table output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum NumberFormat {
+    /// As Arrow displays them, e.g. 1234567.5.
+    #[default]
+    Plain,
+    /// With thousands separators, e.g. 1,234,567.5.
+    Grouped,
+    /// Floats in scientific notation, e.g. 1.2345675e6.
+    Scientific,
+}
+
+/// How numbers, and with a locale dates, are written in table output, along
+/// with geospatial values; machine formats ignore these.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NumberOptions {
+    pub format: NumberFormat,
+    /// Digits after the decimal point of floats and decimals, or `None` for
+    /// as many as needed.
+    pub float_precision: Option<usize>,
+    /// Separators and date order from `--locale`, or `None` for Arrow's own.
+    pub locale: Option<Locale>,
+    /// How `GEOGRAPHY` and `GEOMETRY` values are written, or `None` for
+    /// GeoJSON as compact JSON and WKB as WKT.
+    pub geo: Option<GeoFormat>,
+}
+
+impl NumberOptions {
+    /// Apply the locale and grouping to a number as Rust displays it.
+    fn localize(&self, value: String, grouped: bool) -> String {
+        if !grouped && self.locale.is_none() {
+            return value;
+        }
+        self.locale.unwrap_or_default().number(&value, grouped)
+    }
+
+    fn integer(&self, value: impl fmt::Display) -> String {
+        self.localize(value.to_string(), self.format == NumberFormat::Grouped)
+    }
+
+    fn float<F: fmt::Display + fmt::LowerExp>(&self, value: F) -> String {
+        let value = match (self.format, self.float_precision) {
+            (NumberFormat::Scientific, Some(precision)) => format!("{:.*e}", precision, value),
+            (NumberFormat::Scientific, None) => format!("{:e}", value),
+            (_, Some(precision)) => format!("{:.*}", precision, value),
+            (_, None) => value.to_string(),
+        };
+        self.localize(value, self.format == NumberFormat::Grouped)
+    }
+
+    /// A decimal (Snowflake's `NUMBER` with a scale), written with its scale
+    /// and, with `--float-precision`, rounded to that many places. Exact
+    /// digits are kept, except in scientific notation.
+    fn decimal(&self, col: &dyn arrow_array::Array, row_idx: usize) -> String {
+        let value = match ArrayFormatter::try_new(col, &FormatOptions::default()) {
+            Ok(formatter) => formatter.value(row_idx).to_string(),
+            Err(_) => return format!("<{:?}>", col.data_type()),
+        };
+        if self.format == NumberFormat::Scientific {
+            return match value.parse::<f64>() {
+                Ok(value) => self.float(value),
+                Err(_) => value,
+            };
+        }
+        let value = match self.float_precision {
+            Some(places) => round_decimal(&value, places),
+            None => value,
+        };
+        self.localize(value, self.format == NumberFormat::Grouped)
+    }
+
+    /// A date, time, or timestamp as Arrow displays it, in ISO 8601, with the
+    /// date reordered for the locale.
+    fn temporal(&self, col: &dyn arrow_array::Array, row_idx: usize) -> String {
+        let formatter = match ArrayFormatter::try_new(col, &FormatOptions::default()) {
+            Ok(formatter) => formatter,
+            Err(_) => return format!("<{:?}>", col.data_type()),
+        };
+        let value = formatter.value(row_idx).to_string();
+        match &self.locale {
+            Some(locale) => locale.date(&value),
+            None => value,
+        }
+    }
+}
+
+/// Round the decimal number `value` (e.g. `-12.345`) half away from zero to
+/// `places` digits after the point, padding with zeros.
+fn round_decimal(value: &str, places: usize) -> String {
+    let (sign, unsigned) = match value.strip_prefix('-') {
+        Some(unsigned) => ("-", unsigned),
+        None => ("", value),
+    };
+    let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    let mut digits: Vec<u8> = integer
+        .bytes()
+        .chain(fraction.bytes().chain(std::iter::repeat(b'0')).take(places))
+        .collect();
+    if fraction
+        .as_bytes()
+        .get(places)
+        .is_some_and(|digit| *digit >= b'5')
+    {
+        let mut idx = digits.len();
+        loop {
+            if idx == 0 {
+                digits.insert(0, b'1');
+                break;
+            }
+            idx -= 1;
+            if digits[idx] == b'9' {
+                digits[idx] = b'0';
+            } else {
+                digits[idx] += 1;
+                break;
+            }
+        }
+    }
+    let digits = String::from_utf8(digits).expect("ASCII digits");
+    let (integer, fraction) = digits.split_at(digits.len() - places);
+    match places {
+        0 => format!("{}{}", sign, integer),
+        _ => format!("{}{}.{}", sign, integer, fraction),
+    }
+}
+
+/// A horizontal rule: `left`, then each column filled with `fill`, joined by
+/// `cross`, then `right`.
+struct Rule {
+    left: &'static str,
+    fill: char,
+    cross: &'static str,
+    right: &'static str,
+}
+
+/// Border characters for one [`BorderStyle`].
+struct Border {
+    left: &'static str,
+    separator: &'static str,
+    right: &'static str,
+    top: Option<Rule>,
+    header: Option<Rule>,
+    bottom: Option<Rule>,
+}
+
+impl BorderStyle {
+    fn border(self) -> Border {
+        match self {
+            Self::Ascii => Border {
+                left: "",
+                separator: " | ",
+                right: " | ",
+                top: None,
+                header: Some(Rule {
+                    left: "",
+                    fill: '-',
+                    cross: "-+-",
+                    right: "-+-",
+                }),
+                bottom: None,
+            },
+            Self::Unicode => Border {
+                left: "│ ",
+                separator: " │ ",
+                right: " │",
+                top: Some(Rule {
+                    left: "┌─",
+                    fill: '─',
+                    cross: "─┬─",
+                    right: "─┐",
+                }),
+                header: Some(Rule {
+                    left: "├─",
+                    fill: '─',
+                    cross: "─┼─",
+                    right: "─┤",
+                }),
+                bottom: Some(Rule {
+                    left: "└─",
+                    fill: '─',
+                    cross: "─┴─",
+                    right: "─┘",
+                }),
+            },
+            Self::None => Border {
+                left: "",
+                separator: "  ",
+                right: "",
+                top: None,
+                header: None,
+                bottom: None,
+            },
+            Self::Markdown => Border {
+                left: "| ",
+                separator: " | ",
+                right: " |",
+                top: None,
+                header: Some(Rule {
+                    left: "|-",
+                    fill: '-',
+                    cross: "-|-",
+                    right: "-|",
+                }),
+                bottom: None,
+            },
+        }
+    }
+}
+
+impl Border {
+    /// Render one row. Cells containing newlines span several lines, with the
+    /// other cells padded out below them.
+    fn row(&self, cells: &[String], widths: &[usize]) -> String {
+        let cell_lines: Vec<Vec<&str>> = cells.iter().map(|cell| cell.lines().collect()).collect();
+        let height = cell_lines.iter().map(Vec::len).max().unwrap_or(0).max(1);
+
+        (0..height)
+            .map(|line_idx| {
+                let cells: Vec<String> = cell_lines
+                    .iter()
+                    .zip(widths)
+                    .map(|(lines, width)| {
+                        let text = lines.get(line_idx).copied().unwrap_or("");
+                        format!("{:width$}", text, width = width)
+                    })
+                    .collect();
+                let line = format!("{}{}{}", self.left, cells.join(self.separator), self.right);
+                if self.right.is_empty() {
+                    line.trim_end().to_string()
+                } else {
+                    line
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn rule(rule: &Rule, widths: &[usize]) -> String {
+        let fills: Vec<String> = widths
+            .iter()
+            .map(|width| rule.fill.to_string().repeat(*width))
+            .collect();
+        format!("{}{}{}", rule.left, fills.join(rule.cross), rule.right)
+    }
+}
+
+/// Options that control how results are rendered.
+#[derive(Clone, Debug, Default)]
+pub struct RenderOptions {
+    pub border: BorderStyle,
+    /// Pretty-print string columns whose values are JSON objects or arrays.
+    pub pretty_json: bool,
+    /// Write 64-bit integers and decimals as JSON strings, so consumers that
+    /// parse numbers as doubles don't lose precision.
+    pub json_numbers_as_strings: bool,
+    /// Compression of Parquet output.
+    pub compression: ParquetCompression,
+    /// Maximum rows per Parquet row group, or `None` for the writer's
+    /// default.
+    pub row_group_size: Option<usize>,
+    /// How numbers are written in table output.
+    pub numbers: NumberOptions,
+    /// Leave out the column-name row of table and CSV output, and the rule
+    /// under it in tables.
+    pub no_header: bool,
+    /// Characters shown of each line of a table cell, with longer lines cut
+    /// short and marked with their full length.
+    pub max_col_width: Option<usize>,
+    /// The table named in INSERT output.
+    pub insert_table: Option<String>,
+    /// End each result in table output with a footer counting its rows,
+    /// batches, and bytes.
+    pub stats: bool,
+}
+
+/// Totals for the `--stats` footer of one result.
+#[derive(Default)]
+struct ResultStats {
+    rows: usize,
+    batches: usize,
+    bytes: usize,
+    /// Whether any batch had more rows than were shown.
+    truncated: bool,
+}
+
+impl ResultStats {
+    fn add(&mut self, batch: &RecordBatch) {
+        self.rows += batch.num_rows();
+        self.batches += 1;
+        self.bytes += batch
+            .columns()
+            .iter()
+            .map(|col| col.get_array_memory_size())
+            .sum::<usize>();
+        self.truncated |= batch.num_rows() > MAX_TABLE_ROWS;
+    }
+
+    fn write(&self, handle: &mut dyn Write) -> Result<()> {
+        let plural = |n: usize| if n == 1 { "" } else { "s" };
+        write!(
+            handle,
+            "\n({} row{} in {} batch{}, about {} in memory",
+            self.rows,
+            plural(self.rows),
+            self.batches,
+            if self.batches == 1 { "" } else { "es" },
+            ByteSize(self.bytes as u64)
+        )?;
+        if self.truncated {
+            write!(
+                handle,
+                "; display truncated to the first {} rows of each batch",
+                MAX_TABLE_ROWS
+            )?;
+        }
+        writeln!(handle, ")")?;
+        Ok(())
+    }
+}
+
+/// Rows of each batch shown in table output.
+const MAX_TABLE_ROWS: usize = 1000;
+
+/// Number of leading values sampled to decide whether a column holds JSON.
+const JSON_SAMPLE_ROWS: usize = 20;
+
+/// Returns, for each column of `batch`, whether it is a string column whose
+/// first non-null values all parse as JSON objects or arrays. Deciding per
+/// column keeps formatting consistent within a column.
+fn json_columns(batch: &RecordBatch) -> Vec<bool> {
+    use arrow_array::cast::AsArray;
+
+    batch
+        .columns()
+        .iter()
+        .map(|col| {
+            if col.data_type() != &DataType::Utf8 {
+                return false;
+            }
+            let sample: Vec<&str> = col
+                .as_string::<i32>()
+                .iter()
+                .flatten()
+                .take(JSON_SAMPLE_ROWS)
+                .collect();
+            !sample.is_empty()
+                && sample.iter().all(|value| {
+                    matches!(
+                        serde_json::from_str::<serde_json::Value>(value),
+                        Ok(serde_json::Value::Object(_) | serde_json::Value::Array(_))
+                    )
+                })
+        })
+        .collect()
+}
+
+/// Pretty-print `value` if it is JSON, or return it unchanged.
+fn pretty_json(value: String) -> String {
+    serde_json::from_str::<serde_json::Value>(&value)
+        .ok()
+        .and_then(|json| serde_json::to_string_pretty(&json).ok())
+        .unwrap_or(value)
+}
+
+/// Cut each line of `value` longer than `max` characters to its first `max`,
+/// followed by `…(N)` giving its full length, so it's clear how much is hidden.
+fn truncate_lines(value: String, max: usize) -> String {
+    if value.lines().all(|line| line.chars().count() <= max) {
+        return value;
+    }
+    value
+        .lines()
+        .map(|line| {
+            let length = line.chars().count();
+            if length <= max {
+                line.to_string()
+            } else {
+                let shown: String = line.chars().take(max).collect();
+                format!("{}…({})", shown, length)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Counts the rows a reader delivers and notes whether fetching failed, so an
+/// error partway through a result can say how much of it was written.
+struct Counted<'a, R> {
+    inner: R,
+    rows: &'a AtomicUsize,
+    failed: &'a AtomicBool,
+}
+
+impl<R: RecordBatchReader> Iterator for Counted<'_, R> {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.inner.next();
+        match &next {
+            Some(Ok(batch)) => {
+                self.rows.fetch_add(batch.num_rows(), Ordering::Relaxed);
+            }
+            Some(Err(_)) => self.failed.store(true, Ordering::Relaxed),
+            None => {}
+        }
+        next
+    }
+}
+
+impl<R: RecordBatchReader> RecordBatchReader for Counted<'_, R> {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+}
+
+/// Write the batches of `reader` to `handle` in the given format.
+///
+/// If fetching fails after some rows were written, say a network drop during
+/// a long read, what was written is kept and flushed, followed by a
+/// `-- fetch interrupted after N rows: <error>` footer: in table output, after
+/// the rows, and for machine-readable formats on stderr, so the partial file
+/// stays parseable as far as it goes.
+pub fn write_output(
+    reader: impl RecordBatchReader + Send,
+    format: OutputFormat,
+    options: &RenderOptions,
+    handle: &mut dyn Write,
+) -> Result<()> {
+    let rows = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let reader = Counted {
+        inner: reader,
+        rows: &rows,
+        failed: &failed,
+    };
+    match write_format(reader, format, options, handle) {
+        Err(e) if failed.load(Ordering::Relaxed) && rows.load(Ordering::Relaxed) > 0 => {
+            let rows = rows.load(Ordering::Relaxed);
+            let footer = format!("-- fetch interrupted after {} rows: {:#}", rows, e);
+            if format == OutputFormat::Table {
+                writeln!(handle, "{}", footer)?;
+            } else {
+                eprintln!("{}", footer);
+            }
+            handle.flush()?;
+            Err(e.context(format!("Fetch interrupted after {} rows", rows)))
+        }
+        result => result,
+    }
+}
+
+fn write_format(
+    reader: impl RecordBatchReader + Send,
+    format: OutputFormat,
+    options: &RenderOptions,
+    handle: &mut dyn Write,
+) -> Result<()> {
+    match format {
+        OutputFormat::Table => write_results(reader, options, handle),
+        OutputFormat::Csv => write_csv(reader, !options.no_header, handle),
+        OutputFormat::ArrowStream => write_arrow_stream(reader, handle),
+        OutputFormat::ArrowFile => write_arrow_file(reader, handle),
+        OutputFormat::Parquet => write_parquet(reader, options, handle),
+        OutputFormat::Insert => {
+            let table = options
+                .insert_table
+                .as_deref()
+                .context("--output insert needs --insert-table")?;
+            insert::write_inserts(reader, table, handle)
+        }
+        OutputFormat::Json => {
+            let mut writer = arrow_json::ArrayWriter::new(handle);
+            for batch in reader {
+                writer.write(&json_batch(batch?, options)?)?;
+            }
+            writer.finish()?;
+            Ok(())
+        }
+        OutputFormat::Ndjson => {
+            let mut writer = arrow_json::LineDelimitedWriter::new(handle);
+            for batch in reader {
+                writer.write(&json_batch(batch?, options)?)?;
+            }
+            writer.finish()?;
+            Ok(())
+        }
+    }
+}
+
+/// Returns true for numeric types that can hold values a double can't
+/// represent exactly.
+fn is_wide_number(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Int64
+            | DataType::UInt64
+            | DataType::Decimal128(_, _)
+            | DataType::Decimal256(_, _)
+    )
+}
+
+/// Prepare `batch` for JSON output, casting wide numbers to strings if
+/// requested.
+fn json_batch(batch: RecordBatch, options: &RenderOptions) -> Result<RecordBatch> {
+    if !options.json_numbers_as_strings {
+        return Ok(batch);
+    }
+    let schema = batch.schema();
+    let mut fields = Vec::with_capacity(schema.fields().len());
+    let mut columns = Vec::with_capacity(schema.fields().len());
+    for (field, column) in schema.fields().iter().zip(batch.columns()) {
+        if is_wide_number(field.data_type()) {
+            fields.push(field.as_ref().clone().with_data_type(DataType::Utf8));
+            columns.push(arrow_cast::cast(column, &DataType::Utf8)?);
+        } else {
+            fields.push(field.as_ref().clone());
+            columns.push(column.clone());
+        }
+    }
+    Ok(RecordBatch::try_new(
+        Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone())),
+        columns,
+    )?)
+}
+
+/// Write the batches of `reader` as an Arrow IPC stream.
+fn write_arrow_stream(reader: impl RecordBatchReader + Send, handle: &mut dyn Write) -> Result<()> {
+    let mut writer = StreamWriter::try_new(handle, &reader.schema())?;
+    for batch in reader {
+        writer.write(&batch?)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+fn write_arrow_file(reader: impl RecordBatchReader + Send, handle: &mut dyn Write) -> Result<()> {
+    let schema = reader.schema();
+    let mut writer = arrow_ipc::writer::FileWriter::try_new(handle, &schema)?;
+    for batch in reader {
+        writer.write(&batch?)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+/// Write a Parquet file. The writer needs a `Send` sink, so it writes to a
+/// buffer that is moved to `handle` after every batch; only the row group
+/// being built and the bytes of the last batch are held in memory.
+fn write_parquet(
+    reader: impl RecordBatchReader + Send,
+    options: &RenderOptions,
+    handle: &mut dyn Write,
+) -> Result<()> {
+    let schema = reader.schema();
+    let mut properties = parquet::file::properties::WriterProperties::builder()
+        .set_compression(options.compression.codec());
+    if let Some(rows) = options.row_group_size {
+        properties = properties.set_max_row_group_size(rows);
+    }
+    let mut buffer = Vec::new();
+    let mut writer = parquet::arrow::ArrowWriter::try_new(
+        &mut buffer,
+        schema.clone(),
+        Some(properties.build()),
+    )?;
+    for batch in reader {
+        writer.write(&batch?)?;
+        // The writer counts the bytes it wrote itself, so taking them out of
+        // the buffer leaves the file's offsets intact.
+        handle.write_all(writer.inner())?;
+        writer.inner_mut().clear();
+    }
+    writer.close()?;
+    handle.write_all(&buffer)?;
+    Ok(())
+}
+
+fn write_csv(
+    reader: impl RecordBatchReader + Send,
+    header: bool,
+    handle: &mut dyn Write,
+) -> Result<()> {
+    let mut writer = arrow_csv::WriterBuilder::new()
+        .with_header(header)
+        .build(handle);
+    for batch in reader {
+        writer.write(&batch?)?;
+    }
+    Ok(())
+}
+
+/// Field metadata key under which the Snowflake driver records a column's
+/// Snowflake type (e.g. `fixed`, `text`, `timestamp_ntz`).
+const SNOWFLAKE_TYPE_KEY: &str = "SNOWFLAKE_TYPE";
+
+/// Describe `schema` as a batch with one row per column: its name, Arrow
+/// type, Snowflake type (if known), and nullability.
+pub fn schema_batch(schema: &Schema) -> RecordBatch {
+    let fields = schema.fields();
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(
+            fields.iter().map(|f| f.name().clone()),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            fields.iter().map(|f| f.data_type().to_string()),
+        )),
+        Arc::new(StringArray::from_iter(
+            fields
+                .iter()
+                .map(|f| f.metadata().get(SNOWFLAKE_TYPE_KEY).cloned()),
+        )),
+        Arc::new(BooleanArray::from_iter(
+            fields.iter().map(|f| Some(f.is_nullable())),
+        )),
+    ];
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("column", DataType::Utf8, false),
+        Field::new("type", DataType::Utf8, false),
+        Field::new("snowflake_type", DataType::Utf8, true),
+        Field::new("nullable", DataType::Boolean, false),
+    ]));
+    RecordBatch::try_new(schema, columns).expect("schema columns match schema")
+}
+
+/// Write `schema` as pretty-printed JSON, in the form Arrow's serde support
+/// reads back.
+pub fn write_schema_json(schema: &Schema, handle: &mut dyn Write) -> Result<()> {
+    serde_json::to_writer_pretty(&mut *handle, schema)?;
+    writeln!(handle)?;
+    Ok(())
+}
+
+/// Write only the header of a result with `schema`, for `--headers-only`:
+/// the column names over a row of their Arrow types in text formats, and an
+/// empty result, which still carries the schema, in binary ones.
+pub fn write_header(
+    schema: &SchemaRef,
+    format: OutputFormat,
+    options: &RenderOptions,
+    handle: &mut dyn Write,
+) -> Result<()> {
+    if format == OutputFormat::Insert {
+        bail!("--output insert has no header; use another format");
+    }
+    if format.is_binary() {
+        let reader = RecordBatchIterator::new(std::iter::empty(), schema.clone());
+        return write_output(reader, format, options, handle);
+    }
+    let fields = schema.fields();
+    let columns: Vec<ArrayRef> = fields
+        .iter()
+        .map(|f| Arc::new(StringArray::from(vec![f.data_type().to_string()])) as ArrayRef)
+        .collect();
+    let header = Arc::new(Schema::new(
+        fields
+            .iter()
+            .map(|f| Field::new(f.name(), DataType::Utf8, false))
+            .collect::<Vec<_>>(),
+    ));
+    let batch = RecordBatch::try_new(header.clone(), columns)?;
+    write_output(
+        RecordBatchIterator::new([Ok(batch)], header),
+        format,
+        options,
+        handle,
+    )
+}
+
+/// Returns the string at `row_idx`, replacing invalid UTF-8 (which a driver can
+/// return, since Arrow data received over the C interface isn't validated)
+/// rather than trusting it.
+fn lossy_string<O: OffsetSizeTrait>(array: &GenericStringArray<O>, row_idx: usize) -> String {
+    let offsets = array.value_offsets();
+    let (start, end) = (offsets[row_idx].as_usize(), offsets[row_idx + 1].as_usize());
+    String::from_utf8_lossy(&array.values()[start..end]).into_owned()
+}
+
+pub fn format_value(
+    col: &dyn arrow_array::Array,
+    field: &arrow_schema::Field,
+    row_idx: usize,
+) -> String {
+    format_number_value(col, field, row_idx, &NumberOptions::default())
+}
+
+/// Like [`format_value`], writing numbers as `numbers` asks.
+pub fn format_number_value(
+    col: &dyn arrow_array::Array,
+    field: &arrow_schema::Field,
+    row_idx: usize,
+    numbers: &NumberOptions,
+) -> String {
+    use arrow_array::cast::AsArray;
+    use arrow_array::Array;
+
+    if col.is_null(row_idx) {
+        return "NULL".to_string();
+    }
+    if geo::is_geo(field) {
+        return geo::format_value(col, row_idx, numbers.geo);
+    }
+
+    match field.data_type() {
+        DataType::Utf8 => lossy_string(col.as_string::<i32>(), row_idx),
+        DataType::LargeUtf8 => lossy_string(col.as_string::<i64>(), row_idx),
+        DataType::Int8 => numbers.integer(
+            col.as_primitive::<arrow_array::types::Int8Type>()
+                .value(row_idx),
+        ),
+        DataType::Int16 => numbers.integer(
+            col.as_primitive::<arrow_array::types::Int16Type>()
+                .value(row_idx),
+        ),
+        DataType::Int32 => numbers.integer(
+            col.as_primitive::<arrow_array::types::Int32Type>()
+                .value(row_idx),
+        ),
+        DataType::Int64 => numbers.integer(
+            col.as_primitive::<arrow_array::types::Int64Type>()
+                .value(row_idx),
+        ),
+        DataType::UInt8 => numbers.integer(
+            col.as_primitive::<arrow_array::types::UInt8Type>()
+                .value(row_idx),
+        ),
+        DataType::UInt16 => numbers.integer(
+            col.as_primitive::<arrow_array::types::UInt16Type>()
+                .value(row_idx),
+        ),
+        DataType::UInt32 => numbers.integer(
+            col.as_primitive::<arrow_array::types::UInt32Type>()
+                .value(row_idx),
+        ),
+        DataType::UInt64 => numbers.integer(
+            col.as_primitive::<arrow_array::types::UInt64Type>()
+                .value(row_idx),
+        ),
+        DataType::Float32 => numbers.float(
+            col.as_primitive::<arrow_array::types::Float32Type>()
+                .value(row_idx),
+        ),
+        DataType::Float64 => numbers.float(
+            col.as_primitive::<arrow_array::types::Float64Type>()
+                .value(row_idx),
+        ),
+        DataType::Boolean => col.as_boolean().value(row_idx).to_string(),
+        DataType::Date32
+        | DataType::Date64
+        | DataType::Time32(_)
+        | DataType::Time64(_)
+        | DataType::Timestamp(_, _) => numbers.temporal(col, row_idx),
+        DataType::Decimal128(_, _) | DataType::Decimal256(_, _) => numbers.decimal(col, row_idx),
+        DataType::Dictionary(_, value_type) => {
+            // Slice first so only this row's key is normalized.
+            let row = col.slice(row_idx, 1);
+            let dictionary = row.as_any_dictionary();
+            let key = dictionary.normalized_keys()[0];
+            let value_field = Field::new(field.name(), value_type.as_ref().clone(), true);
+            format_number_value(dictionary.values().as_ref(), &value_field, key, numbers)
+        }
+        DataType::Map(entries, _) => {
+            let DataType::Struct(entry_fields) = entries.data_type() else {
+                return format!("<{:?}>", field.data_type());
+            };
+            let map = col.as_map().value(row_idx);
+            let pairs: Vec<String> = (0..map.len())
+                .map(|idx| {
+                    format!(
+                        "{}: {}",
+                        format_number_value(map.column(0).as_ref(), &entry_fields[0], idx, numbers),
+                        format_number_value(map.column(1).as_ref(), &entry_fields[1], idx, numbers)
+                    )
+                })
+                .collect();
+            format!("{{{}}}", pairs.join(", "))
+        }
+        _ => format!("<{:?}>", field.data_type()),
+    }
+}
+
+/// Write the batches of `reader` as text tables.
+pub fn write_results(
+    reader: impl RecordBatchReader + Send,
+    options: &RenderOptions,
+    handle: &mut dyn Write,
+) -> Result<()> {
+    let border = options.border.border();
+    let mut first_batch = true;
+    // Decided from the first non-empty batch. Markdown cells can't span lines, so JSON stays compact there.
+    let mut json: Option<Vec<bool>> = None;
+    let mut stats = ResultStats::default();
+
+    for batch_result in reader {
+        let batch = batch_result?;
+        let schema = batch.schema();
+        let num_rows = batch.num_rows();
+        stats.add(&batch);
+
+        if num_rows == 0 {
+            if first_batch {
+                writeln!(handle, "Query returned no rows.")?;
+            }
+            continue;
+        }
+
+        let cell = |value: String| {
+            if options.border == BorderStyle::Markdown {
+                value.replace('|', "\\|")
+            } else {
+                value
+            }
+        };
+
+        let header: Vec<String> = schema
+            .fields()
+            .iter()
+            .map(|field| cell(field.name().clone()))
+            .collect();
+        let json = json.get_or_insert_with(|| {
+            if options.pretty_json && options.border != BorderStyle::Markdown {
+                json_columns(&batch)
+            } else {
+                vec![false; batch.num_columns()]
+            }
+        });
+        let rows: Vec<Vec<String>> = (0..num_rows.min(MAX_TABLE_ROWS))
+            .map(|row_idx| {
+                schema
+                    .fields()
+                    .iter()
+                    .zip(batch.columns())
+                    .zip(json.iter())
+                    .map(|((field, col), &is_json)| {
+                        let value =
+                            format_number_value(col.as_ref(), field, row_idx, &options.numbers);
+                        let truncate = |value: String| match options.max_col_width {
+                            Some(max) => truncate_lines(value, max),
+                            None => value,
+                        };
+                        if is_json && !col.is_null(row_idx) {
+                            truncate(pretty_json(value))
+                        } else {
+                            cell(truncate(value))
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut col_widths: Vec<usize> = if options.no_header {
+            vec![0; header.len()]
+        } else {
+            header.iter().map(|name| name.len().max(10)).collect()
+        };
+        for row in &rows {
+            for (width, value) in col_widths.iter_mut().zip(row) {
+                let value_width = value
+                    .lines()
+                    .map(|line| line.chars().count())
+                    .max()
+                    .unwrap_or(0);
+                *width = (*width).max(value_width);
+            }
+        }
+
+        if let Some(rule) = &border.top {
+            writeln!(handle, "{}", Border::rule(rule, &col_widths))?;
+        }
+        if !options.no_header {
+            writeln!(handle, "{}", border.row(&header, &col_widths))?;
+            if let Some(rule) = &border.header {
+                writeln!(handle, "{}", Border::rule(rule, &col_widths))?;
+            }
+        }
+        for row in &rows {
+            writeln!(handle, "{}", border.row(row, &col_widths))?;
+        }
+        if let Some(rule) = &border.bottom {
+            writeln!(handle, "{}", Border::rule(rule, &col_widths))?;
+        }
+
+        if num_rows > MAX_TABLE_ROWS {
+            writeln!(
+                handle,
+                "\n... (showing first {} of {} rows)",
+                MAX_TABLE_ROWS, num_rows
+            )?;
+        }
+
+        first_batch = false;
+    }
+
+    if options.stats && stats.batches > 0 {
+        stats.write(handle)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::Int64Array;
+
+    use super::*;
+
+    fn batch(name: &str, column: ArrayRef) -> RecordBatch {
+        let field = Field::new(name, column.data_type().clone(), true);
+        RecordBatch::try_new(Arc::new(Schema::new(vec![field])), vec![column]).unwrap()
+    }
+
+    #[test]
+    fn write_result_across_batches() {
+        let first = batch("ID", Arc::new(Int64Array::from(vec![1])));
+        let second = batch("ID", Arc::new(Int64Array::from(vec![2])));
+        let schema = first.schema();
+        let reader = RecordBatchIterator::new(vec![Ok(first), Ok(second)], schema);
+
+        let mut out = Vec::new();
+        write_results(reader, &RenderOptions::default(), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out.matches("ID").count(), 2);
+        assert!(out.contains("1 ") && out.contains("2 "));
+    }
+
+    #[test]
+    fn truncate_long_cells() {
+        let long = "x".repeat(30);
+        let column = Arc::new(StringArray::from(vec![
+            Some(long.as_str()),
+            Some("short"),
+            None,
+        ]));
+        let options = RenderOptions {
+            max_col_width: Some(8),
+            ..Default::default()
+        };
+        let batch = batch("NOTE", column);
+        let schema = batch.schema();
+        let mut out = Vec::new();
+        write_results(
+            RecordBatchIterator::new([Ok(batch)], schema),
+            &options,
+            &mut out,
+        )
+        .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[2], "xxxxxxxx…(30) | ");
+        assert_eq!(lines[3], "short         | ");
+
+        assert_eq!(
+            truncate_lines("{\n  \"a\": \"bcdef\"\n}".to_string(), 6),
+            "{\n  \"a\":…(14)\n}"
+        );
+        assert_eq!(truncate_lines("héllo".to_string(), 5), "héllo");
+    }
+
+    #[test]
+    fn write_stats_footer() {
+        let first = batch("ID", Arc::new(Int64Array::from_iter_values(0..1500)));
+        let second = batch("ID", Arc::new(Int64Array::from(vec![1])));
+        let bytes = first.get_array_memory_size() + second.get_array_memory_size();
+        let schema = first.schema();
+        let reader = RecordBatchIterator::new(vec![Ok(first), Ok(second)], schema);
+        let options = RenderOptions {
+            stats: true,
+            ..Default::default()
+        };
+
+        let mut out = Vec::new();
+        write_results(reader, &options, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.ends_with(&format!(
+            "\n(1501 rows in 2 batches, about {} in memory; display truncated to the first \
+             1000 rows of each batch)\n",
+            ByteSize(bytes as u64)
+        )));
+    }
+
+    #[test]
+    fn write_without_header() {
+        let batch = batch("ID", Arc::new(Int64Array::from(vec![1, 22])));
+        let options = RenderOptions {
+            no_header: true,
+            ..Default::default()
+        };
+        let write = |format| {
+            let reader = RecordBatchIterator::new(vec![Ok(batch.clone())], batch.schema());
+            let mut out = Vec::new();
+            write_output(reader, format, &options, &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!(write(OutputFormat::Csv), "1\n22\n");
+        assert_eq!(write(OutputFormat::Table), "1  | \n22 | \n");
+    }
+
+    #[test]
+    fn stream_parquet_row_groups() {
+        /// Counts the bytes written, keeping them.
+        struct Counting(Arc<AtomicUsize>, Vec<u8>);
+
+        impl Write for Counting {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.fetch_add(buf.len(), Ordering::Relaxed);
+                self.1.write(buf)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let written = Arc::new(AtomicUsize::new(0));
+        let seen = written.clone();
+        let ids = batch("ID", Arc::new(Int64Array::from_iter_values(0..10_000)));
+        let schema = ids.schema();
+        // By the last batch, the full row groups before it were written.
+        let batches = (0..4).map(move |i| {
+            if i == 3 {
+                assert!(seen.load(Ordering::Relaxed) > 0);
+            }
+            Ok(ids.clone())
+        });
+        let options = RenderOptions {
+            row_group_size: Some(10_000),
+            compression: ParquetCompression::None,
+            ..Default::default()
+        };
+        let mut out = Counting(written, Vec::new());
+        write_output(
+            RecordBatchIterator::new(batches, schema),
+            OutputFormat::Parquet,
+            &options,
+            &mut out,
+        )
+        .unwrap();
+        let reader = parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(
+            bytes::Bytes::from(out.1),
+        )
+        .unwrap();
+        assert_eq!(reader.metadata().num_row_groups(), 4);
+        let rows: usize = reader.build().unwrap().map(|b| b.unwrap().num_rows()).sum();
+        assert_eq!(rows, 40_000);
+    }
+
+    #[test]
+    fn report_interrupted_fetch() {
+        let first = batch("ID", Arc::new(Int64Array::from(vec![1, 2])));
+        let schema = first.schema();
+        let reader = RecordBatchIterator::new(
+            vec![
+                Ok(first),
+                Err(ArrowError::IoError(
+                    "connection reset".to_string(),
+                    std::io::Error::other("reset"),
+                )),
+            ],
+            schema,
+        );
+        let mut out = Vec::new();
+        let err = write_output(
+            reader,
+            OutputFormat::Table,
+            &RenderOptions::default(),
+            &mut out,
+        )
+        .unwrap_err();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("ID         | \n"), "{}", out);
+        assert!(
+            out.ends_with("-- fetch interrupted after 2 rows: Io error: connection reset: reset\n"),
+            "{}",
+            out
+        );
+        assert!(err
+            .to_string()
+            .starts_with("Fetch interrupted after 2 rows"));
+    }
+
+    #[test]
+    fn write_wide_integers_exactly() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("I", DataType::Int64, false),
+            Field::new("U", DataType::UInt64, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![i64::MIN, i64::MAX])),
+                Arc::new(arrow_array::UInt64Array::from(vec![0, u64::MAX])),
+            ],
+        )
+        .unwrap();
+        let write = |format, options: &RenderOptions| {
+            let reader = RecordBatchIterator::new(vec![Ok(batch.clone())], schema.clone());
+            let mut out = Vec::new();
+            write_output(reader, format, options, &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let (min, max, umax) = (i64::MIN, i64::MAX, u64::MAX);
+
+        let table = write(OutputFormat::Table, &RenderOptions::default());
+        assert!(table.contains(&format!("{} | 0", min)), "{}", table);
+        assert!(table.contains(&format!("{}  | {}", max, umax)), "{}", table);
+        let grouped = RenderOptions {
+            numbers: NumberOptions {
+                format: NumberFormat::Grouped,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let table = write(OutputFormat::Table, &grouped);
+        assert!(table.contains("18,446,744,073,709,551,615"), "{}", table);
+        assert!(table.contains("-9,223,372,036,854,775,808"), "{}", table);
+
+        assert_eq!(
+            write(OutputFormat::Csv, &RenderOptions::default()),
+            format!("I,U\n{},0\n{},{}\n", min, max, umax)
+        );
+        assert_eq!(
+            write(OutputFormat::Ndjson, &RenderOptions::default()),
+            format!(
+                "{{\"I\":{},\"U\":0}}\n{{\"I\":{},\"U\":{}}}\n",
+                min, max, umax
+            )
+        );
+        let json = write(
+            OutputFormat::Json,
+            &RenderOptions {
+                json_numbers_as_strings: true,
+                ..Default::default()
+            },
+        );
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed[0]["I"], min.to_string());
+        assert_eq!(parsed[1]["U"], umax.to_string());
+    }
+
+    #[test]
+    fn write_csv_output() {
+        let first = batch("ID", Arc::new(Int64Array::from(vec![1, 2])));
+        let schema = first.schema();
+        let reader = RecordBatchIterator::new(vec![Ok(first)], schema);
+
+        let mut out = Vec::new();
+        write_output(
+            reader,
+            OutputFormat::Csv,
+            &RenderOptions::default(),
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "ID\n1\n2\n");
+    }
+
+    #[test]
+    fn border_styles() {
+        let render = |border| {
+            let first = batch("ID", Arc::new(Int64Array::from(vec![1])));
+            let schema = first.schema();
+            let reader = RecordBatchIterator::new(vec![Ok(first)], schema);
+            let mut out = Vec::new();
+            let options = RenderOptions {
+                border,
+                ..Default::default()
+            };
+            write_results(reader, &options, &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        assert_eq!(
+            render(BorderStyle::Ascii),
+            "ID         | \n-----------+-\n1          | \n"
+        );
+        assert_eq!(render(BorderStyle::None), "ID\n1\n");
+        assert_eq!(
+            render(BorderStyle::Markdown),
+            "| ID         |\n|------------|\n| 1          |\n"
+        );
+        let unicode = render(BorderStyle::Unicode);
+        assert!(unicode.starts_with("┌────────────┐\n│ ID         │\n├"));
+        assert!(unicode.ends_with("└────────────┘\n"));
+    }
+
+    #[test]
+    fn write_arrow_stream_output() {
+        let first = batch("ID", Arc::new(Int64Array::from(vec![1, 2])));
+        let second = batch("ID", Arc::new(Int64Array::from(vec![3])));
+        let schema = first.schema();
+        let reader = RecordBatchIterator::new(vec![Ok(first.clone()), Ok(second.clone())], schema);
+
+        let mut out = Vec::new();
+        write_output(
+            reader,
+            OutputFormat::ArrowStream,
+            &RenderOptions::default(),
+            &mut out,
+        )
+        .unwrap();
+
+        let batches = arrow_ipc::reader::StreamReader::try_new(std::io::Cursor::new(out), None)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(batches, vec![first, second]);
+    }
+
+    #[test]
+    fn pretty_print_json_columns() {
+        let json = batch(
+            "DOC",
+            Arc::new(StringArray::from(vec![Some(r#"{"a":1}"#), None])),
+        );
+        let schema = json.schema();
+        let reader = RecordBatchIterator::new(vec![Ok(json)], schema);
+        let options = RenderOptions {
+            pretty_json: true,
+            ..Default::default()
+        };
+
+        let mut out = Vec::new();
+        write_results(reader, &options, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "DOC        | \n-----------+-\n{          | \n  \"a\": 1   | \n}          | \nNULL       | \n"
+        );
+
+        // A column with any non-JSON value in the sample is left alone.
+        let mixed = batch("S", Arc::new(StringArray::from(vec!["[1]", "plain"])));
+        assert_eq!(json_columns(&mixed), [false]);
+    }
+
+    #[test]
+    fn describe_schema() {
+        let mut metadata = std::collections::HashMap::new();
+        metadata.insert(SNOWFLAKE_TYPE_KEY.to_string(), "fixed".to_string());
+        let schema = Schema::new(vec![
+            Field::new("ID", DataType::Int64, false).with_metadata(metadata),
+            Field::new("NAME", DataType::Utf8, true),
+        ]);
+
+        let batch = schema_batch(&schema);
+        let row = |idx| -> Vec<String> {
+            batch
+                .schema()
+                .fields()
+                .iter()
+                .zip(batch.columns())
+                .map(|(field, col)| format_value(col.as_ref(), field, idx))
+                .collect()
+        };
+        assert_eq!(row(0), ["ID", "Int64", "fixed", "false"]);
+        assert_eq!(row(1), ["NAME", "Utf8", "NULL", "true"]);
+    }
+
+    #[test]
+    fn write_schema_as_json() {
+        let schema = Schema::new(vec![
+            Field::new("ID", DataType::Int64, false),
+            Field::new("PRICE", DataType::Decimal128(38, 2), true)
+                .with_metadata([("SNOWFLAKE_TYPE".to_string(), "fixed".to_string())].into()),
+        ]);
+        let mut out = Vec::new();
+        write_schema_json(&schema, &mut out).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(json["fields"][0]["name"], "ID");
+        assert_eq!(json["fields"][1]["metadata"]["SNOWFLAKE_TYPE"], "fixed");
+        assert_eq!(serde_json::from_slice::<Schema>(&out).unwrap(), schema);
+    }
+
+    #[test]
+    fn write_headers_only() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("ID", DataType::Int64, false),
+            Field::new("NAME", DataType::Utf8, true),
+        ]));
+        let header = |format| {
+            let mut out = Vec::new();
+            write_header(&schema, format, &RenderOptions::default(), &mut out).unwrap();
+            out
+        };
+        assert_eq!(header(OutputFormat::Csv), b"ID,NAME\nInt64,Utf8\n");
+        assert_eq!(
+            header(OutputFormat::Ndjson),
+            b"{\"ID\":\"Int64\",\"NAME\":\"Utf8\"}\n"
+        );
+        let table = String::from_utf8(header(OutputFormat::Table)).unwrap();
+        assert_eq!(
+            table,
+            "ID         | NAME       | \n\
+             -----------+------------+-\n\
+             Int64      | Utf8       | \n"
+        );
+
+        let stream = header(OutputFormat::ArrowStream);
+        let reader = arrow_ipc::reader::StreamReader::try_new(stream.as_slice(), None).unwrap();
+        assert_eq!(reader.schema(), schema);
+        assert_eq!(reader.count(), 0);
+    }
+
+    #[test]
+    fn lossy_decode_invalid_utf8() {
+        let offsets = arrow_buffer::OffsetBuffer::from_lengths([2, 3]);
+        let values = arrow_buffer::Buffer::from(b"ok\xffab".to_vec());
+        // SAFETY: deliberately invalid UTF-8, as a driver could return.
+        let array = unsafe { StringArray::new_unchecked(offsets, values, None) };
+        let field = Field::new("S", DataType::Utf8, true);
+        assert_eq!(format_value(&array, &field, 0), "ok");
+        assert_eq!(format_value(&array, &field, 1), "\u{fffd}ab");
+    }
+
+    #[test]
+    fn json_numbers_as_strings() {
+        let ids = batch(
+            "ID",
+            Arc::new(Int64Array::from(vec![Some(9007199254740993), None])),
+        );
+        let render = |format, json_numbers_as_strings| {
+            let schema = ids.schema();
+            let reader = RecordBatchIterator::new(vec![Ok(ids.clone())], schema);
+            let options = RenderOptions {
+                json_numbers_as_strings,
+                ..Default::default()
+            };
+            let mut out = Vec::new();
+            write_output(reader, format, &options, &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        assert_eq!(
+            render(OutputFormat::Ndjson, false),
+            "{\"ID\":9007199254740993}\n{}\n"
+        );
+        assert_eq!(
+            render(OutputFormat::Json, true),
+            "[{\"ID\":\"9007199254740993\"},{}]"
+        );
+    }
+
+    #[test]
+    fn format_maps_and_dictionaries() {
+        use arrow_array::builder::{Int64Builder, MapBuilder, StringBuilder};
+        use arrow_array::types::Int32Type;
+        use arrow_array::{Array, DictionaryArray};
+
+        let mut builder = MapBuilder::new(None, StringBuilder::new(), Int64Builder::new());
+        builder.keys().append_value("a");
+        builder.values().append_value(1);
+        builder.keys().append_value("b");
+        builder.values().append_null();
+        builder.append(true).unwrap();
+        builder.append(true).unwrap();
+        builder.append(false).unwrap();
+        let maps = batch("M", Arc::new(builder.finish()));
+        let field = maps.schema().field(0).clone();
+        let column = maps.column(0);
+        assert_eq!(format_value(column.as_ref(), &field, 0), "{a: 1, b: NULL}");
+        assert_eq!(format_value(column.as_ref(), &field, 1), "{}");
+        assert_eq!(format_value(column.as_ref(), &field, 2), "NULL");
+
+        let dictionary: DictionaryArray<Int32Type> =
+            vec![Some("low"), Some("high"), None, Some("low")]
+                .into_iter()
+                .collect();
+        assert_eq!(dictionary.values().len(), 2);
+        let levels = batch("LEVEL", Arc::new(dictionary));
+        let field = levels.schema().field(0).clone();
+        let column = levels.column(0);
+        let values: Vec<String> = (0..column.len())
+            .map(|idx| format_value(column.as_ref(), &field, idx))
+            .collect();
+        assert_eq!(values, ["low", "high", "NULL", "low"]);
+    }
+
+    #[test]
+    fn write_file_formats() {
+        let ids = batch("ID", Arc::new(Int64Array::from_iter_values(0..10)));
+        let write = |format, options: &RenderOptions| {
+            let schema = ids.schema();
+            let reader = RecordBatchIterator::new(vec![Ok(ids.clone())], schema);
+            let mut out = Vec::new();
+            write_output(reader, format, options, &mut out).unwrap();
+            bytes::Bytes::from(out)
+        };
+
+        let arrow = write(OutputFormat::ArrowFile, &RenderOptions::default());
+        let batches = arrow_ipc::reader::FileReader::try_new(std::io::Cursor::new(arrow), None)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(batches, vec![ids.clone()]);
+
+        let options = RenderOptions {
+            compression: ParquetCompression::Snappy,
+            row_group_size: Some(4),
+            ..Default::default()
+        };
+        let reader = parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(write(
+            OutputFormat::Parquet,
+            &options,
+        ))
+        .unwrap();
+        let metadata = reader.metadata().clone();
+        assert_eq!(metadata.num_row_groups(), 3);
+        assert_eq!(
+            metadata.row_group(0).column(0).compression(),
+            parquet::basic::Compression::SNAPPY
+        );
+        let batches = reader
+            .build()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            arrow_select::concat::concat_batches(&ids.schema(), &batches).unwrap(),
+            ids
+        );
+
+        assert_eq!(
+            OutputFormat::from_path(Path::new("out.Parquet")),
+            Some(OutputFormat::Parquet)
+        );
+        assert_eq!(
+            OutputFormat::from_path(Path::new("out.feather")),
+            Some(OutputFormat::ArrowFile)
+        );
+        assert_eq!(OutputFormat::from_path(Path::new("out")), None);
+    }
+
+    #[test]
+    fn format_scaled_decimals() {
+        let amounts = arrow_array::Decimal128Array::from(vec![1250, 123456789, -5])
+            .with_precision_and_scale(10, 2)
+            .unwrap();
+        let table = |numbers: NumberOptions| {
+            let options = RenderOptions {
+                numbers,
+                ..Default::default()
+            };
+            let batch = batch("AMOUNT", Arc::new(amounts.clone()));
+            let schema = batch.schema();
+            let mut out = Vec::new();
+            write_results(
+                RecordBatchIterator::new([Ok(batch)], schema),
+                &options,
+                &mut out,
+            )
+            .unwrap();
+            String::from_utf8(out)
+                .unwrap()
+                .lines()
+                .skip(2)
+                .map(|line| line.trim_end_matches(" | ").trim_end().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            table(NumberOptions::default()),
+            ["12.50", "1234567.89", "-0.05"]
+        );
+        let grouped = NumberOptions {
+            format: NumberFormat::Grouped,
+            ..Default::default()
+        };
+        assert_eq!(table(grouped), ["12.50", "1,234,567.89", "-0.05"]);
+        let german = NumberOptions {
+            format: NumberFormat::Grouped,
+            float_precision: Some(1),
+            locale: Some("de-DE".parse().unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(table(german), ["12,5", "1.234.567,9", "-0,1"]);
+
+        assert_eq!(round_decimal("0.995", 2), "1.00");
+        assert_eq!(round_decimal("-99.5", 0), "-100");
+        assert_eq!(round_decimal("12", 2), "12.00");
+        assert_eq!(round_decimal("1.2345", 3), "1.235");
+    }
+
+    #[test]
+    fn format_numbers() {
+        let group_digits = |number| Locale::default().number(number, true);
+        assert_eq!(group_digits("1234567"), "1,234,567");
+        assert_eq!(group_digits("-123456.789"), "-123,456.789");
+        assert_eq!(group_digits("999"), "999");
+        assert_eq!(group_digits("NaN"), "NaN");
+
+        let grouped = NumberOptions {
+            format: NumberFormat::Grouped,
+            float_precision: Some(2),
+            ..Default::default()
+        };
+        let ints = Int64Array::from(vec![1234567]);
+        let field = Field::new("N", DataType::Int64, false);
+        assert_eq!(format_value(&ints, &field, 0), "1234567");
+        assert_eq!(format_number_value(&ints, &field, 0, &grouped), "1,234,567");
+        assert_eq!(grouped.float(1234.5678f64), "1,234.57");
+        assert_eq!(grouped.float(0.1f32), "0.10");
+
+        let scientific = NumberOptions {
+            format: NumberFormat::Scientific,
+            float_precision: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(scientific.float(1234567.5f64), "1.235e6");
+        assert_eq!(scientific.integer(1234567), "1234567");
+        assert_eq!(NumberOptions::default().float(0.1f32), "0.1");
+
+        let german = NumberOptions {
+            locale: Some("de-DE".parse().unwrap()),
+            ..grouped
+        };
+        assert_eq!(german.float(1234.5678f64), "1.234,57");
+        assert_eq!(german.integer(1234567), "1.234.567");
+        let dates = arrow_array::Date32Array::from(vec![19787]);
+        let field = Field::new("D", DataType::Date32, false);
+        assert_eq!(format_value(&dates, &field, 0), "2024-03-05");
+        assert_eq!(
+            format_number_value(&dates, &field, 0, &german),
+            "05.03.2024"
+        );
+    }
+}
@@ -0,0 +1,179 @@
+//! Resolving each connection field from a command-line flag, an environment
+//! variable, or the profile, in that order of precedence, so CI can supply
+//! secrets and per-run settings without editing the config file.
+//!
+//! Precedence is per field: `--role` overrides `SNOWFLAKE_ROLE`, which
+//! overrides the profile's `role`, while the profile still supplies every
+//! field set by neither. When the profile connects with a URI, an override
+//! replaces the URI's value for that field.
+
+use anyhow::{Context, Result};
+use url::Url;
+
+use crate::{uri_dsn, uri_fields, Profile};
+
+/// The fields that can be overridden, with the environment variable for each.
+/// Secrets have no flag, since command lines are visible to other users.
+pub const FIELDS: [(&str, &str); 8] = [
+    ("account", "SNOWFLAKE_ACCOUNT"),
+    ("user", "SNOWFLAKE_USER"),
+    ("password", "SNOWFLAKE_PASSWORD"),
+    ("private_key", "SNOWFLAKE_PRIVATE_KEY"),
+    ("role", "SNOWFLAKE_ROLE"),
+    ("warehouse", "SNOWFLAKE_WAREHOUSE"),
+    ("database", "SNOWFLAKE_DATABASE"),
+    ("schema", "SNOWFLAKE_SCHEMA"),
+];
+
+/// The values that override profile fields, by field name.
+#[derive(Clone, Debug, Default)]
+pub struct Overrides(Vec<(&'static str, String)>);
+
+impl Overrides {
+    /// Take each field from `flags`, by field name, or else from its
+    /// environment variable as read by `var`. Empty values are ignored.
+    pub fn new(flags: &[(&str, Option<&String>)], var: impl Fn(&str) -> Option<String>) -> Self {
+        let values = FIELDS
+            .iter()
+            .filter_map(|&(field, env)| {
+                let flag = flags
+                    .iter()
+                    .find(|(name, _)| *name == field)
+                    .and_then(|(_, value)| value.cloned());
+                flag.or_else(|| var(env))
+                    .filter(|value| !value.is_empty())
+                    .map(|value| (field, value))
+            })
+            .collect();
+        Overrides(values)
+    }
+
+    /// `profile` with the overridden fields replaced. A field its URI sets is
+    /// replaced in the URI, and one with a `_command` no longer runs it.
+    pub fn apply(&self, profile: &Profile) -> Result<Profile> {
+        let mut profile = profile.clone();
+        for (field, value) in &self.0 {
+            let in_uri = match &profile.uri {
+                Some(uri) => uri_fields(uri)?.contains(field),
+                None => false,
+            };
+            if in_uri {
+                let uri = profile.uri.as_deref().unwrap_or_default();
+                profile.uri = Some(set_uri_field(uri, field, value)?);
+            }
+            let value = (!in_uri).then(|| value.clone());
+            match *field {
+                "account" => profile.account = value,
+                "user" => profile.user = value,
+                "password" => {
+                    profile.password = value;
+                    profile.password_command = None;
+                }
+                "private_key" => {
+                    profile.private_key = value;
+                    profile.private_key_command = None;
+                }
+                "role" => profile.role = value,
+                "warehouse" => profile.warehouse = value,
+                "database" => profile.database = value,
+                "schema" => profile.schema = value,
+                _ => unreachable!("unknown field {}", field),
+            }
+        }
+        Ok(profile)
+    }
+}
+
+/// Replace `field` in a connection URI of the form
+/// `user[:password]@account/database/schema?warehouse=...&role=...`.
+fn set_uri_field(uri: &str, field: &str, value: &str) -> Result<String> {
+    let mut url =
+        Url::parse(&format!("snowflake://{}", uri_dsn(uri))).context("Invalid connection URI")?;
+    let invalid = || anyhow::anyhow!("Can't set {} in the connection URI", field);
+    match field {
+        "user" => url.set_username(value).map_err(|_| invalid())?,
+        "password" => url.set_password(Some(value)).map_err(|_| invalid())?,
+        "account" => url.set_host(Some(value)).map_err(|_| invalid())?,
+        "database" | "schema" => {
+            let mut segments: Vec<String> = url
+                .path_segments()
+                .map(|segments| {
+                    segments
+                        .filter(|s| !s.is_empty())
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default();
+            let idx = usize::from(field == "schema");
+            *segments.get_mut(idx).ok_or_else(invalid)? = value.to_string();
+            url.path_segments_mut()
+                .map_err(|_| invalid())?
+                .clear()
+                .extend(&segments);
+        }
+        _ => {
+            let pairs: Vec<(String, String)> = url
+                .query_pairs()
+                .map(|(key, existing)| {
+                    let existing = if key.eq_ignore_ascii_case(field) {
+                        value.to_string()
+                    } else {
+                        existing.into_owned()
+                    };
+                    (key.into_owned(), existing)
+                })
+                .collect();
+            url.query_pairs_mut().clear().extend_pairs(pairs);
+        }
+    }
+    let dsn = &url.as_str()["snowflake://".len()..];
+    Ok(if uri.starts_with("snowflake://") {
+        format!("snowflake://{}", dsn)
+    } else {
+        dsn.to_string()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_then_environment_then_profile() {
+        let role = "FLAG_ROLE".to_string();
+        let env = |name: &str| match name {
+            "SNOWFLAKE_ROLE" => Some("ENV_ROLE".to_string()),
+            "SNOWFLAKE_PASSWORD" => Some("env-secret".to_string()),
+            "SNOWFLAKE_WAREHOUSE" => Some(String::new()),
+            _ => None,
+        };
+        let overrides = Overrides::new(&[("role", Some(&role)), ("user", None)], env);
+        let profile = Profile {
+            user: Some("me".to_string()),
+            role: Some("PROFILE_ROLE".to_string()),
+            warehouse: Some("WH".to_string()),
+            password_command: Some("pass show snowflake".to_string()),
+            ..Default::default()
+        };
+        let resolved = overrides.apply(&profile).unwrap();
+        assert_eq!(resolved.role.as_deref(), Some("FLAG_ROLE"));
+        assert_eq!(resolved.password.as_deref(), Some("env-secret"));
+        assert_eq!(resolved.password_command, None);
+        assert_eq!(resolved.user.as_deref(), Some("me"));
+        assert_eq!(resolved.warehouse.as_deref(), Some("WH"));
+
+        let profile = Profile {
+            uri: Some("snowflake://me@acct/DB/PUBLIC?warehouse=WH&role=R".to_string()),
+            ..Default::default()
+        };
+        let schema = "STAGING".to_string();
+        let overrides = Overrides::new(&[("schema", Some(&schema))], env);
+        let resolved = overrides.apply(&profile).unwrap();
+        assert_eq!(
+            resolved.uri.as_deref(),
+            Some("snowflake://me@acct/DB/STAGING?warehouse=WH&role=ENV_ROLE")
+        );
+        assert_eq!(resolved.role, None);
+        assert_eq!(resolved.password.as_deref(), Some("env-secret"));
+    }
+}
@@ -0,0 +1,252 @@
+//! Named bind parameters: `--param name=value` fills each `:name` placeholder
+//! in a query.
+//!
+//! The Snowflake driver binds parameters by position, so placeholders are
+//! rewritten to `?` and bound from a single-row batch with a column, named
+//! after the parameter, for each occurrence. Only the names given with
+//! `--param` are rewritten, so `v:field` paths and other uses of `:` in
+//! queries without parameters run as written. Values are bound as text, which
+//! Snowflake converts as it would a string literal; cast the placeholder
+//! (`:id::INT`) where the conversion is ambiguous.
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::Result;
+use arrow_array::{ArrayRef, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+
+/// A `name=value` parameter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Param {
+    pub name: String,
+    pub value: String,
+}
+
+impl FromStr for Param {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, value) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected NAME=VALUE, got '{}'", s))?;
+        let name = name.trim();
+        if !is_name(name) {
+            return Err(format!(
+                "parameter names are letters, digits, and underscores, not starting with a \
+                 digit; got '{}'",
+                name
+            ));
+        }
+        Ok(Param {
+            name: name.to_string(),
+            value: value.to_string(),
+        })
+    }
+}
+
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Whether a `:` after `prev` starts a semi-structured path, as in `v:field`,
+/// `$1:a`, `"V":a`, or `f(x):a`, rather than a placeholder.
+fn is_path(prev: Option<char>) -> bool {
+    prev.is_some_and(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '$' | ')' | ']' | '"'))
+}
+
+/// Find the placeholders of `sql` for the names `declared` accepts, skipping
+/// string literals, quoted identifiers, comments, `$$` bodies, `::` casts, and
+/// `<expr>:path` lookups. Returns `sql` with each placeholder replaced by `?`,
+/// and the names in order of appearance.
+fn placeholders(sql: &str, declared: impl Fn(&str) -> bool) -> (String, Vec<String>) {
+    let mut rewritten = String::with_capacity(sql.len());
+    let mut names = Vec::new();
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' => {
+                rewritten.push(c);
+                for next in chars.by_ref() {
+                    rewritten.push(next);
+                    if next == c {
+                        break;
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'$') => {
+                rewritten.push_str("$$");
+                chars.next();
+                let mut prev = ' ';
+                for next in chars.by_ref() {
+                    rewritten.push(next);
+                    if prev == '$' && next == '$' {
+                        break;
+                    }
+                    prev = next;
+                }
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                rewritten.push(c);
+                for next in chars.by_ref() {
+                    rewritten.push(next);
+                    if next == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                rewritten.push(c);
+                let mut prev = ' ';
+                for next in chars.by_ref() {
+                    rewritten.push(next);
+                    if prev == '*' && next == '/' {
+                        break;
+                    }
+                    prev = next;
+                }
+            }
+            ':' if chars.peek() == Some(&':') => {
+                rewritten.push_str("::");
+                chars.next();
+            }
+            ':' if !is_path(rewritten.chars().next_back())
+                && chars
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_alphabetic() || *c == '_') =>
+            {
+                let mut name = String::new();
+                while let Some(&next) = chars.peek() {
+                    if !(next.is_ascii_alphanumeric() || next == '_') {
+                        break;
+                    }
+                    name.push(next);
+                    chars.next();
+                }
+                if declared(&name) {
+                    rewritten.push('?');
+                    names.push(name);
+                } else {
+                    rewritten.push(':');
+                    rewritten.push_str(&name);
+                }
+            }
+            _ => rewritten.push(c),
+        }
+    }
+    (rewritten, names)
+}
+
+fn find<'a>(params: &'a [Param], name: &str) -> Option<&'a Param> {
+    params
+        .iter()
+        .rev()
+        .find(|param| param.name.eq_ignore_ascii_case(name))
+}
+
+/// Rewrite the `:name` placeholders of `sql` that have a `--param` for
+/// binding, returning the SQL to run and the batch to bind, or `sql` unchanged
+/// if it has none. Other `:name`s are left as they are, so queries without
+/// `--param` run untouched. Names are case-insensitive, like Snowflake's
+/// unquoted identifiers.
+pub fn bind_named(sql: &str, params: &[Param]) -> Result<(String, Option<RecordBatch>)> {
+    if params.is_empty() {
+        return Ok((sql.to_string(), None));
+    }
+    let (rewritten, names) = placeholders(sql, |name| find(params, name).is_some());
+    if names.is_empty() {
+        return Ok((sql.to_string(), None));
+    }
+
+    let fields: Vec<Field> = names
+        .iter()
+        .map(|name| Field::new(name, DataType::Utf8, false))
+        .collect();
+    let columns: Vec<ArrayRef> = names
+        .iter()
+        .filter_map(|name| find(params, name))
+        .map(|param| Arc::new(StringArray::from(vec![param.value.clone()])) as ArrayRef)
+        .collect();
+    let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)?;
+    Ok((rewritten, Some(batch)))
+}
+
+/// Warn about parameters that no statement of `script` uses, which are
+/// likely typos.
+pub fn warn_unused(script: &str, params: &[Param]) {
+    let (_, names) = placeholders(script, |name| find(params, name).is_some());
+    for param in params {
+        if !names
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(&param.name))
+        {
+            eprintln!(
+                "Warning: --param {} given but the query has no :{}",
+                param.name, param.name
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::cast::AsArray;
+
+    use super::*;
+
+    #[test]
+    fn parse_params() {
+        assert_eq!(
+            "region=EMEA".parse(),
+            Ok(Param {
+                name: "region".to_string(),
+                value: "EMEA".to_string(),
+            })
+        );
+        assert_eq!("q=a=b".parse::<Param>().unwrap().value, "a=b");
+        assert!("region".parse::<Param>().is_err());
+        assert!("1x=2".parse::<Param>().is_err());
+    }
+
+    #[test]
+    fn bind_placeholders() {
+        let sql = "SELECT ':skip', x::INT, \"a:b\" -- :comment\n\
+                   FROM t WHERE region = :region AND id = :id::INT OR r2 = :REGION";
+        let params = vec!["region=EMEA".parse().unwrap(), "id=42".parse().unwrap()];
+        let (rewritten, batch) = bind_named(sql, &params).unwrap();
+        assert_eq!(
+            rewritten,
+            "SELECT ':skip', x::INT, \"a:b\" -- :comment\n\
+             FROM t WHERE region = ? AND id = ?::INT OR r2 = ?"
+        );
+        let batch = batch.unwrap();
+        assert_eq!(batch.num_rows(), 1);
+        let names: Vec<&str> = batch
+            .schema_ref()
+            .fields()
+            .iter()
+            .map(|f| f.name().as_str())
+            .collect();
+        assert_eq!(names, ["region", "id", "REGION"]);
+        assert_eq!(batch.column(2).as_string::<i32>().value(0), "EMEA");
+
+        // Undeclared names, paths, and procedure bodies are left alone.
+        let sql = "SELECT v:a, $1:b, \"V\":c, f(x):d, :undeclared, :region FROM t; \
+                   CREATE PROCEDURE p() AS $$ SELECT :region $$";
+        let (rewritten, batch) = bind_named(sql, &params).unwrap();
+        assert_eq!(
+            rewritten,
+            "SELECT v:a, $1:b, \"V\":c, f(x):d, :undeclared, ? FROM t; \
+             CREATE PROCEDURE p() AS $$ SELECT :region $$"
+        );
+        assert_eq!(batch.unwrap().num_columns(), 1);
+        assert_eq!(
+            bind_named("SELECT v:a FROM t", &[]).unwrap(),
+            ("SELECT v:a FROM t".to_string(), None)
+        );
+    }
+}
@@ -0,0 +1,312 @@
+//! Client-side pivoting of a fetched result, for `--pivot`, to cross-tabulate
+//! a small result without writing Snowflake's `PIVOT` syntax.
+//!
+//! Each distinct value of the `rows` column becomes a row and each distinct
+//! value of the `cols` column a column, both in order of first appearance, so
+//! sorting the input with `--order-by` orders them too. Each cell aggregates
+//! the `values` column over the input rows with that pair of keys.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use arrow_array::cast::AsArray;
+use arrow_array::types::{Decimal128Type, Float64Type, Int64Type};
+use arrow_array::{
+    Array, ArrayRef, ArrowPrimitiveType, Int64Array, PrimitiveArray, RecordBatch, UInt32Array,
+};
+use arrow_cast::display::{ArrayFormatter, FormatOptions};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use arrow_select::concat::concat_batches;
+use arrow_select::take::take;
+
+/// How the values in each cell are combined.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Aggregate {
+    /// The sum of the non-null values, or NULL if there are none.
+    #[default]
+    Sum,
+    /// The number of non-null values.
+    Count,
+    /// The first non-null value, in input order.
+    First,
+}
+
+/// A pivot, given as `rows=COLUMN,cols=COLUMN,values=COLUMN[,agg=sum|count|first]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pivot {
+    rows: String,
+    cols: String,
+    values: String,
+    aggregate: Aggregate,
+}
+
+impl FromStr for Pivot {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let usage = || {
+            format!(
+                "expected rows=COLUMN,cols=COLUMN,values=COLUMN[,agg=sum|count|first], got '{}'",
+                spec
+            )
+        };
+        let (mut rows, mut cols, mut values) = (None, None, None);
+        let mut aggregate = Aggregate::default();
+        for part in spec.split(',') {
+            let (key, value) = part.split_once('=').ok_or_else(usage)?;
+            let value = value.trim();
+            if value.is_empty() {
+                return Err(usage());
+            }
+            match key.trim().to_ascii_lowercase().as_str() {
+                "rows" => rows = Some(value.to_string()),
+                "cols" => cols = Some(value.to_string()),
+                "values" => values = Some(value.to_string()),
+                "agg" => {
+                    aggregate = match value.to_ascii_lowercase().as_str() {
+                        "sum" => Aggregate::Sum,
+                        "count" => Aggregate::Count,
+                        "first" => Aggregate::First,
+                        _ => return Err(format!("expected sum, count, or first, got '{}'", value)),
+                    }
+                }
+                _ => return Err(usage()),
+            }
+        }
+        match (rows, cols, values) {
+            (Some(rows), Some(cols), Some(values)) => Ok(Pivot {
+                rows,
+                cols,
+                values,
+                aggregate,
+            }),
+            _ => Err(usage()),
+        }
+    }
+}
+
+/// The column of `batch` named `name`, matching case-insensitively if there's
+/// no exact match since Snowflake upper-cases unquoted names.
+fn column(batch: &RecordBatch, name: &str) -> Result<(Field, ArrayRef)> {
+    let schema = batch.schema();
+    let idx = schema
+        .index_of(name)
+        .ok()
+        .or_else(|| {
+            schema
+                .fields()
+                .iter()
+                .position(|f| f.name().eq_ignore_ascii_case(name))
+        })
+        .with_context(|| format!("--pivot: no column named {}", name))?;
+    Ok((schema.field(idx).clone(), batch.column(idx).clone()))
+}
+
+/// Number each row of `col` by its distinct value, in order of first
+/// appearance, returning the numbers, the first row of each value, and each
+/// value as displayed.
+fn group(col: &dyn Array) -> Result<(Vec<usize>, Vec<u32>, Vec<String>)> {
+    let formatter = ArrayFormatter::try_new(col, &FormatOptions::default())?;
+    let mut ids = HashMap::new();
+    let mut groups = Vec::with_capacity(col.len());
+    let (mut firsts, mut names) = (Vec::new(), Vec::new());
+    for row_idx in 0..col.len() {
+        let key = col
+            .is_valid(row_idx)
+            .then(|| formatter.value(row_idx).to_string());
+        let id = *ids.entry(key.clone()).or_insert_with(|| {
+            firsts.push(row_idx as u32);
+            names.push(key.unwrap_or_else(|| "NULL".to_string()));
+            names.len() - 1
+        });
+        groups.push(id);
+    }
+    Ok((groups, firsts, names))
+}
+
+/// Sum the valid values of `values` in each of `cells`, or NULL for a cell
+/// without any.
+fn sum<T: ArrowPrimitiveType>(
+    values: &PrimitiveArray<T>,
+    cells: &[Vec<usize>],
+    add: impl Fn(T::Native, T::Native) -> Option<T::Native>,
+) -> Result<PrimitiveArray<T>> {
+    cells
+        .iter()
+        .map(|cell| {
+            let mut total = None;
+            for &row_idx in cell.iter().filter(|&&row_idx| values.is_valid(row_idx)) {
+                let value = values.value(row_idx);
+                total = Some(match total {
+                    None => value,
+                    Some(total) => add(total, value).context("--pivot: sum overflowed")?,
+                });
+            }
+            Ok(total)
+        })
+        .collect()
+}
+
+/// Aggregate `values` over the rows of each of `cells`.
+fn aggregate(
+    values: &ArrayRef,
+    name: &str,
+    cells: &[Vec<usize>],
+    aggregate: Aggregate,
+) -> Result<ArrayRef> {
+    Ok(match aggregate {
+        Aggregate::Count => Arc::new(Int64Array::from_iter_values(cells.iter().map(|cell| {
+            cell.iter()
+                .filter(|&&row_idx| values.is_valid(row_idx))
+                .count() as i64
+        }))),
+        Aggregate::First => {
+            let indices = UInt32Array::from_iter(cells.iter().map(|cell| {
+                cell.iter()
+                    .find(|&&row_idx| values.is_valid(row_idx))
+                    .map(|&row_idx| row_idx as u32)
+            }));
+            take(values.as_ref(), &indices, None)?
+        }
+        Aggregate::Sum => match values.data_type() {
+            dt if dt.is_integer() => {
+                let values = arrow_cast::cast(values, &DataType::Int64)?;
+                Arc::new(sum(
+                    values.as_primitive::<Int64Type>(),
+                    cells,
+                    i64::checked_add,
+                )?)
+            }
+            DataType::Float16 | DataType::Float32 | DataType::Float64 => {
+                let values = arrow_cast::cast(values, &DataType::Float64)?;
+                Arc::new(sum(values.as_primitive::<Float64Type>(), cells, |a, b| {
+                    Some(a + b)
+                })?)
+            }
+            DataType::Decimal128(_, scale) => Arc::new(
+                sum(
+                    values.as_primitive::<Decimal128Type>(),
+                    cells,
+                    i128::checked_add,
+                )?
+                .with_precision_and_scale(38, *scale)?,
+            ),
+            dt => bail!(
+                "--pivot can't sum {} column {}; add agg=count or agg=first",
+                dt,
+                name
+            ),
+        },
+    })
+}
+
+/// Pivot `batches` as `pivot` asks into a single batch: the `rows` column,
+/// then one column per distinct value of the `cols` column.
+pub fn pivot(schema: &SchemaRef, batches: &[RecordBatch], pivot: &Pivot) -> Result<RecordBatch> {
+    let batch = concat_batches(schema, batches)?;
+    let (row_field, rows) = column(&batch, &pivot.rows)?;
+    let (_, cols) = column(&batch, &pivot.cols)?;
+    let (value_field, values) = column(&batch, &pivot.values)?;
+
+    let (row_groups, row_firsts, _) = group(rows.as_ref())?;
+    let (col_groups, _, col_names) = group(cols.as_ref())?;
+    // The input rows in each cell, by column and then row.
+    let mut cells = vec![vec![Vec::new(); row_firsts.len()]; col_names.len()];
+    for (row_idx, (&row, &col)) in row_groups.iter().zip(&col_groups).enumerate() {
+        cells[col][row].push(row_idx);
+    }
+
+    let mut fields = vec![row_field.with_nullable(true)];
+    let mut columns = vec![take(rows.as_ref(), &UInt32Array::from(row_firsts), None)?];
+    for (name, cells) in col_names.iter().zip(&cells) {
+        let column = aggregate(&values, value_field.name(), cells, pivot.aggregate)?;
+        fields.push(Field::new(name, column.data_type().clone(), true));
+        columns.push(column);
+    }
+    Ok(RecordBatch::try_new(
+        Arc::new(Schema::new(fields)),
+        columns,
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::StringArray;
+
+    use super::*;
+
+    #[test]
+    fn pivot_rows_by_columns() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("REGION", DataType::Utf8, true),
+            Field::new("QUARTER", DataType::Utf8, true),
+            Field::new("REVENUE", DataType::Int64, true),
+        ]));
+        let batch = |regions: Vec<&str>, quarters: Vec<Option<&str>>, revenue: Vec<Option<i64>>| {
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(StringArray::from(regions)),
+                    Arc::new(StringArray::from(quarters)),
+                    Arc::new(Int64Array::from(revenue)),
+                ],
+            )
+            .unwrap()
+        };
+        let batches = [
+            batch(
+                vec!["EU", "US", "EU"],
+                vec![Some("Q1"), Some("Q1"), Some("Q2")],
+                vec![Some(10), Some(20), None],
+            ),
+            batch(
+                vec!["EU", "US"],
+                vec![Some("Q1"), None],
+                vec![Some(5), Some(7)],
+            ),
+        ];
+        let cell = |batch: &RecordBatch, col: usize| -> Vec<String> {
+            let formatter =
+                ArrayFormatter::try_new(batch.column(col).as_ref(), &FormatOptions::default())
+                    .unwrap();
+            (0..batch.num_rows())
+                .map(|row_idx| formatter.value(row_idx).to_string())
+                .collect()
+        };
+
+        let spec: Pivot = "rows=region,cols=quarter,values=revenue".parse().unwrap();
+        let sums = pivot(&schema, &batches, &spec).unwrap();
+        let names: Vec<&str> = sums
+            .schema_ref()
+            .fields()
+            .iter()
+            .map(|f| f.name().as_str())
+            .collect();
+        assert_eq!(names, ["REGION", "Q1", "Q2", "NULL"]);
+        assert_eq!(cell(&sums, 0), ["EU", "US"]);
+        assert_eq!(cell(&sums, 1), ["15", "20"]);
+        assert_eq!(cell(&sums, 2), ["", ""]);
+        assert_eq!(cell(&sums, 3), ["", "7"]);
+
+        let spec: Pivot = "rows=REGION,cols=QUARTER,values=REVENUE,agg=count"
+            .parse()
+            .unwrap();
+        let counts = pivot(&schema, &batches, &spec).unwrap();
+        assert_eq!(cell(&counts, 1), ["2", "1"]);
+        assert_eq!(cell(&counts, 2), ["0", "0"]);
+
+        let spec: Pivot = "rows=QUARTER,cols=REGION,values=REGION".parse().unwrap();
+        assert!(pivot(&schema, &batches, &spec).is_err());
+        let spec: Pivot = "rows=QUARTER,cols=REGION,values=REGION,agg=first"
+            .parse()
+            .unwrap();
+        let firsts = pivot(&schema, &batches, &spec).unwrap();
+        assert_eq!(cell(&firsts, 0), ["Q1", "Q2", ""]);
+        assert_eq!(cell(&firsts, 1), ["EU", "EU", ""]);
+
+        assert!("rows=A,cols=B".parse::<Pivot>().is_err());
+        assert!("rows=A,cols=B,values=C,agg=avg".parse::<Pivot>().is_err());
+    }
+}
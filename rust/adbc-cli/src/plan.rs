@@ -0,0 +1,256 @@
+//! Query plans from `EXPLAIN USING JSON`, for `--explain-json`.
+//!
+//! Snowflake returns the plan as one JSON document: `GlobalStats` for the
+//! whole query, and `Operations`, a list of steps each holding a flat list of
+//! operators that name their `parentOperators` by id. The operators are
+//! nested into a tree so the plan reads top-down, from the result to the
+//! table scans.
+
+use std::io::Write;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::size::ByteSize;
+
+/// The pruning Snowflake expects for a scan, or for the whole query.
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Stats {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partitions_assigned: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partitions_total: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes_assigned: Option<u64>,
+}
+
+impl Stats {
+    fn parse(value: &Value) -> Self {
+        let stat = |name: &str| value.get(name).and_then(Value::as_u64);
+        Stats {
+            partitions_assigned: stat("partitionsAssigned"),
+            partitions_total: stat("partitionsTotal"),
+            bytes_assigned: stat("bytesAssigned"),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == Stats::default()
+    }
+
+    fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let (Some(assigned), Some(total)) = (self.partitions_assigned, self.partitions_total) {
+            parts.push(format!("{} of {} partitions", assigned, total));
+        }
+        if let Some(bytes) = self.bytes_assigned {
+            parts.push(ByteSize(bytes).to_string());
+        }
+        parts.join(", ")
+    }
+}
+
+/// An operator of the plan and the operators feeding it.
+#[derive(Debug, Serialize)]
+pub struct Node {
+    pub id: u64,
+    pub operation: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub objects: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub expressions: Vec<String>,
+    #[serde(flatten)]
+    pub stats: Stats,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Node>,
+}
+
+/// A parsed plan: the query's stats and, for each step, its root operators.
+#[derive(Debug, Serialize)]
+pub struct Plan {
+    pub global_stats: Stats,
+    pub steps: Vec<Vec<Node>>,
+}
+
+fn strings(value: &Value, key: &str) -> Vec<String> {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .map(|values| {
+            values
+                .iter()
+                .map(|value| match value {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Nest the operators of one step under their parents. An operator with
+/// several parents (a shared subplan) is shown under the first.
+fn nest(operators: &[Value]) -> Result<Vec<Node>> {
+    let mut parsed = Vec::with_capacity(operators.len());
+    for operator in operators {
+        let id = operator
+            .get("id")
+            .and_then(Value::as_u64)
+            .context("EXPLAIN plan has an operation without an id")?;
+        let parent = operator
+            .get("parentOperators")
+            .and_then(Value::as_array)
+            .and_then(|parents| parents.first())
+            .and_then(Value::as_u64);
+        let node = Node {
+            id,
+            operation: operator
+                .get("operation")
+                .and_then(Value::as_str)
+                .unwrap_or("?")
+                .to_string(),
+            objects: strings(operator, "objects"),
+            expressions: strings(operator, "expressions"),
+            stats: Stats::parse(operator),
+            children: Vec::new(),
+        };
+        parsed.push((parent, node));
+    }
+
+    fn attach(id: u64, parsed: &mut Vec<(Option<u64>, Node)>) -> Vec<Node> {
+        let mut children = Vec::new();
+        let mut idx = 0;
+        while idx < parsed.len() {
+            if parsed[idx].0 == Some(id) && parsed[idx].1.id != id {
+                children.push(parsed.remove(idx).1);
+            } else {
+                idx += 1;
+            }
+        }
+        for child in &mut children {
+            child.children = attach(child.id, parsed);
+        }
+        children
+    }
+
+    let known: Vec<u64> = parsed.iter().map(|(_, node)| node.id).collect();
+    let mut roots = Vec::new();
+    let mut idx = 0;
+    while idx < parsed.len() {
+        if !parsed[idx].0.is_some_and(|parent| known.contains(&parent)) {
+            roots.push(parsed.remove(idx).1);
+        } else {
+            idx += 1;
+        }
+    }
+    for root in &mut roots {
+        root.children = attach(root.id, &mut parsed);
+    }
+    Ok(roots)
+}
+
+/// Parse the plan `EXPLAIN USING JSON` returned. The plan may come back as a
+/// JSON document or, from a VARIANT cell, as a JSON string holding one.
+pub fn parse(cell: &str) -> Result<Plan> {
+    let mut plan: Value = serde_json::from_str(cell).context("EXPLAIN returned invalid JSON")?;
+    if let Value::String(inner) = &plan {
+        plan = serde_json::from_str(inner).context("EXPLAIN returned invalid JSON")?;
+    }
+    let steps = match plan.get("Operations") {
+        Some(Value::Array(steps)) => steps
+            .iter()
+            .map(|step| nest(step.as_array().map_or(&[], Vec::as_slice)))
+            .collect::<Result<_>>()?,
+        _ => anyhow::bail!("EXPLAIN plan has no Operations"),
+    };
+    Ok(Plan {
+        global_stats: plan
+            .get("GlobalStats")
+            .map(Stats::parse)
+            .unwrap_or_default(),
+        steps,
+    })
+}
+
+fn write_node(node: &Node, depth: usize, handle: &mut dyn Write) -> Result<()> {
+    let mut line = format!("{}{}", "  ".repeat(depth), node.operation);
+    if !node.objects.is_empty() {
+        line.push_str(&format!(" {}", node.objects.join(", ")));
+    }
+    if !node.stats.is_empty() {
+        line.push_str(&format!(" ({})", node.stats.describe()));
+    }
+    writeln!(handle, "{}", line)?;
+    for expression in &node.expressions {
+        writeln!(handle, "{}  | {}", "  ".repeat(depth), expression)?;
+    }
+    for child in &node.children {
+        write_node(child, depth + 1, handle)?;
+    }
+    Ok(())
+}
+
+/// Write `plan` as an indented tree, each operator above its inputs with its
+/// expressions on the lines below it.
+pub fn write_tree(plan: &Plan, handle: &mut dyn Write) -> Result<()> {
+    if !plan.global_stats.is_empty() {
+        writeln!(handle, "Scan: {}", plan.global_stats.describe())?;
+    }
+    for (idx, step) in plan.steps.iter().enumerate() {
+        if plan.steps.len() > 1 {
+            writeln!(handle, "Step {}:", idx + 1)?;
+        }
+        for root in step {
+            write_node(root, 0, handle)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAN: &str = r#"{
+        "GlobalStats": {"partitionsTotal": 500, "partitionsAssigned": 120, "bytesAssigned": 2048},
+        "Operations": [[
+            {"id": 0, "operation": "Result", "expressions": ["T.ID", "T.NAME"]},
+            {"id": 2, "parentOperators": [1], "operation": "TableScan",
+             "objects": ["DB.PUBLIC.T"], "expressions": ["ID", "NAME"],
+             "partitionsAssigned": 120, "partitionsTotal": 500, "bytesAssigned": 2048},
+            {"id": 1, "parentOperators": [0], "operation": "Filter",
+             "expressions": ["T.ID > 10"]}
+        ]]
+    }"#;
+
+    #[test]
+    fn parse_plan_tree() {
+        let plan = parse(PLAN).unwrap();
+        assert_eq!(plan.global_stats.partitions_assigned, Some(120));
+        let mut out = Vec::new();
+        write_tree(&plan, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Scan: 120 of 500 partitions, 2.0 KB\n\
+             Result\n  \
+             | T.ID\n  \
+             | T.NAME\n  \
+             Filter\n    \
+             | T.ID > 10\n    \
+             TableScan DB.PUBLIC.T (120 of 500 partitions, 2.0 KB)\n    \
+             \x20 | ID\n    \
+             \x20 | NAME\n"
+        );
+
+        let json = serde_json::to_value(&plan).unwrap();
+        let scan = &json["steps"][0][0]["children"][0]["children"][0];
+        assert_eq!(scan["operation"], "TableScan");
+        assert_eq!(scan["bytes_assigned"], 2048);
+
+        // A VARIANT cell can hold the plan as a JSON string.
+        let quoted = serde_json::to_string(PLAN).unwrap();
+        assert_eq!(parse(&quoted).unwrap().steps[0].len(), 1);
+        assert!(parse("{}").is_err());
+    }
+}
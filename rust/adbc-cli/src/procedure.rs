@@ -0,0 +1,91 @@
+//! Several result sets from one stored procedure call.
+//!
+//! ADBC returns a single result per statement, and a Snowflake procedure
+//! returns a single value or table. A procedure producing several result sets
+//! instead returns their query IDs as an array, e.g.
+//! `RETURN ARRAY_CONSTRUCT(:first_id, :second_id)` after saving each `SQLID`,
+//! and each is then fetched with `RESULT_SCAN`.
+
+use arrow_array::RecordBatch;
+
+use crate::output::format_value;
+use crate::sql;
+
+/// Returns true if `s` looks like a Snowflake query ID, a UUID such as
+/// `01b2c3d4-0000-1234-0000-000100020003`.
+fn is_query_id(s: &str) -> bool {
+    let groups: Vec<&str> = s.split('-').collect();
+    groups.len() == 5
+        && groups
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(group, len)| group.len() == len && group.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// The query IDs of the result sets a `CALL` in `sql` returned as `batches`:
+/// a single value holding a JSON array of two or more query IDs. Any other
+/// result is the procedure's only result.
+pub fn result_ids(sql: &str, batches: &[RecordBatch]) -> Option<Vec<String>> {
+    if sql::first_keyword(sql).as_deref() != Some("CALL") {
+        return None;
+    }
+    let mut rows = batches.iter().filter(|batch| batch.num_rows() > 0);
+    let batch = rows.next()?;
+    if rows.next().is_some() || batch.num_rows() != 1 || batch.num_columns() != 1 {
+        return None;
+    }
+    let value = format_value(batch.column(0).as_ref(), batch.schema().field(0), 0);
+    let ids: Vec<String> = serde_json::from_str(&value).ok()?;
+    (ids.len() > 1 && ids.iter().all(|id| is_query_id(id))).then_some(ids)
+}
+
+/// The query fetching the result of the query `id`.
+pub fn result_scan_sql(id: &str) -> String {
+    format!("SELECT * FROM TABLE(RESULT_SCAN('{}'))", id)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::StringArray;
+    use arrow_schema::{DataType, Field, Schema};
+
+    use super::*;
+
+    fn value(value: &str) -> Vec<RecordBatch> {
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "REPORT",
+            DataType::Utf8,
+            true,
+        )]));
+        vec![RecordBatch::try_new(schema, vec![Arc::new(StringArray::from(vec![value]))]).unwrap()]
+    }
+
+    #[test]
+    fn procedure_with_two_result_sets() {
+        let returned = value(
+            r#"[
+  "01b2c3d4-0000-1234-0000-000100020003",
+  "01b2c3d4-0000-1234-0000-000100020007"
+]"#,
+        );
+        let ids = result_ids("CALL daily_report()", &returned).unwrap();
+        assert_eq!(
+            result_scan_sql(&ids[1]),
+            "SELECT * FROM TABLE(RESULT_SCAN('01b2c3d4-0000-1234-0000-000100020007'))"
+        );
+        assert_eq!(ids.len(), 2);
+
+        assert_eq!(result_ids("SELECT PARSE_JSON(x)", &returned), None);
+        assert_eq!(result_ids("CALL p()", &value("42")), None);
+        assert_eq!(result_ids("CALL p()", &value(r#"["a", "b"]"#)), None);
+        assert_eq!(
+            result_ids(
+                "CALL p()",
+                &value(r#"["01b2c3d4-0000-1234-0000-000100020003"]"#)
+            ),
+            None
+        );
+    }
+}
@@ -0,0 +1,65 @@
+//! The interactive prompt, rendered from a template such as
+//! `{warehouse}:{database}> ` so it's obvious which environment queries run
+//! against.
+
+/// The prompt used when neither `--prompt` nor the profile sets one.
+pub const DEFAULT: &str = "adbc> ";
+
+/// Placeholders whose values are looked up from the session.
+const SESSION_PLACEHOLDERS: &[&str] = &["{database}", "{schema}", "{warehouse}", "{role}"];
+
+/// SQL returning the session's context, in the order of [`Session`]'s fields.
+pub const SESSION_SQL: &str =
+    "SELECT CURRENT_DATABASE(), CURRENT_SCHEMA(), CURRENT_WAREHOUSE(), CURRENT_ROLE()";
+
+/// Values substituted into a prompt template.
+#[derive(Debug, Default)]
+pub struct Session {
+    pub profile: String,
+    pub database: Option<String>,
+    pub schema: Option<String>,
+    pub warehouse: Option<String>,
+    pub role: Option<String>,
+}
+
+/// Returns true if `template` refers to the session's context, which needs a
+/// query to look up.
+pub fn needs_session(template: &str) -> bool {
+    SESSION_PLACEHOLDERS.iter().any(|p| template.contains(p))
+}
+
+/// Render `template`, replacing each placeholder with its value, or `?` if
+/// the session has none. Unknown placeholders are left as they are.
+pub fn render(template: &str, session: &Session) -> String {
+    let value = |v: &Option<String>| v.clone().unwrap_or_else(|| "?".to_string());
+    template
+        .replace("{profile}", &session.profile)
+        .replace("{database}", &value(&session.database))
+        .replace("{schema}", &value(&session.schema))
+        .replace("{warehouse}", &value(&session.warehouse))
+        .replace("{role}", &value(&session.role))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_prompts() {
+        let session = Session {
+            profile: "prod".to_string(),
+            database: Some("ANALYTICS".to_string()),
+            warehouse: Some("WH_XS".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(render(DEFAULT, &session), "adbc> ");
+        assert_eq!(
+            render("{profile} {warehouse}:{database}.{schema}> ", &session),
+            "prod WH_XS:ANALYTICS.?> "
+        );
+        assert_eq!(render("{user}> ", &session), "{user}> ");
+
+        assert!(!needs_session("{profile}> "));
+        assert!(needs_session("{role}> "));
+    }
+}
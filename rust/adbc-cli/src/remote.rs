@@ -0,0 +1,116 @@
+//! Fetching config files from remote locations such as `https://` URLs.
+//!
+//! Each URI scheme is handled by a [`ConfigResolver`]. Backends for secret
+//! managers (e.g. `secretsmanager://`) can be added by implementing the trait
+//! and registering the resolver in [`resolvers`].
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Mutex, OnceLock};
+
+use anyhow::{anyhow, Context, Result};
+
+pub type ResolveFuture<'a> = Pin<Box<dyn Future<Output = Result<String>> + Send + 'a>>;
+
+/// Resolves a config location to the config file's contents.
+pub trait ConfigResolver: Send + Sync {
+    /// The URI scheme handled by this resolver, without `://`.
+    fn scheme(&self) -> &'static str;
+
+    /// Fetch the contents of `location`.
+    fn resolve<'a>(&'a self, location: &'a str) -> ResolveFuture<'a>;
+}
+
+/// Fetches config files over HTTPS.
+pub struct HttpsResolver;
+
+impl ConfigResolver for HttpsResolver {
+    fn scheme(&self) -> &'static str {
+        "https"
+    }
+
+    fn resolve<'a>(&'a self, location: &'a str) -> ResolveFuture<'a> {
+        Box::pin(async move {
+            let response = reqwest::get(location)
+                .await
+                .with_context(|| format!("Network error while fetching config from {}", location))?
+                .error_for_status()
+                .with_context(|| format!("Failed to fetch config from {}", location))?;
+            response
+                .text()
+                .await
+                .with_context(|| format!("Network error while reading config from {}", location))
+        })
+    }
+}
+
+fn resolvers() -> Vec<Box<dyn ConfigResolver>> {
+    vec![Box::new(HttpsResolver)]
+}
+
+/// Returns the scheme of `location` if it looks like a URI rather than a path.
+pub fn scheme(location: &str) -> Option<&str> {
+    location
+        .split_once("://")
+        .map(|(scheme, _)| scheme)
+        .filter(|scheme| {
+            !scheme.is_empty()
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '+')
+        })
+}
+
+/// Fetch the contents of a remote config location. Results are cached for the
+/// lifetime of the process, so each location is fetched at most once.
+pub async fn fetch(location: &str) -> Result<String> {
+    static CACHE: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+    let cache = CACHE.get_or_init(Default::default);
+
+    if let Some(content) = cache.lock().unwrap().get(location) {
+        return Ok(content.clone());
+    }
+
+    let scheme = scheme(location).with_context(|| format!("Not a URI: {}", location))?;
+    let resolvers = resolvers();
+    let resolver = resolvers
+        .iter()
+        .find(|resolver| resolver.scheme() == scheme)
+        .ok_or_else(|| {
+            let supported: Vec<_> = resolvers.iter().map(|r| r.scheme()).collect();
+            anyhow!(
+                "Unsupported config location scheme '{}' (supported: {})",
+                scheme,
+                supported.join(", ")
+            )
+        })?;
+
+    let content = resolver.resolve(location).await?;
+    cache
+        .lock()
+        .unwrap()
+        .insert(location.to_string(), content.clone());
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_schemes() {
+        assert_eq!(scheme("https://example.com/config.yaml"), Some("https"));
+        assert_eq!(scheme("secretsmanager://prod/adbc"), Some("secretsmanager"));
+        assert_eq!(scheme("config.yaml"), None);
+        assert_eq!(scheme("./dir://odd.yaml"), None);
+    }
+
+    #[tokio::test]
+    async fn rejects_unsupported_schemes() {
+        let err = fetch("s3://bucket/config.yaml").await.unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Unsupported config location scheme 's3'"));
+    }
+}
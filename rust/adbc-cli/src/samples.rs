@@ -0,0 +1,118 @@
+//! Per-iteration benchmark samples, written with `benchmark --bench-csv` for
+//! plotting or statistics tools that want every measurement rather than the
+//! summary.
+//!
+//! The columns are a stable interface: they keep their names and order, and
+//! new ones are only ever added at the end, so scripts reading the file keep
+//! working across versions. Measurements a client doesn't report are empty.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+/// The CSV header. Only ever append to it.
+pub const COLUMNS: [&str; 9] = [
+    "iteration",
+    "client",
+    "total_ms",
+    "connect_ms",
+    "execute_ms",
+    "first_batch_ms",
+    "drain_ms",
+    "rows",
+    "bytes",
+];
+
+/// The phases of one iteration, each lasting from the end of the previous
+/// one, and the Arrow memory its batches used, where the client reports them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Phases {
+    /// Logging in, if the iteration opened a connection.
+    pub connect: Option<Duration>,
+    /// Creating and executing the statement.
+    pub execute: Option<Duration>,
+    /// Waiting for the first batch.
+    pub first_batch: Option<Duration>,
+    /// Reading the remaining batches.
+    pub drain: Option<Duration>,
+    pub bytes: Option<usize>,
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{:.3}", duration.as_secs_f64() * 1000.0)
+}
+
+/// Write one row per iteration of `client` to `handle`, from each
+/// iteration's time, rows, and, where known, phases.
+pub fn write(
+    client: &str,
+    times: &[Duration],
+    row_counts: &[usize],
+    phases: &[Phases],
+    handle: &mut dyn Write,
+) -> Result<()> {
+    writeln!(handle, "{}", COLUMNS.join(","))?;
+    for (idx, (time, rows)) in times.iter().zip(row_counts).enumerate() {
+        let phases = phases.get(idx).copied().unwrap_or_default();
+        let optional = |duration: Option<Duration>| duration.map(millis).unwrap_or_default();
+        writeln!(
+            handle,
+            "{},{},{},{},{},{},{},{},{}",
+            idx + 1,
+            client,
+            millis(*time),
+            optional(phases.connect),
+            optional(phases.execute),
+            optional(phases.first_batch),
+            optional(phases.drain),
+            rows,
+            phases.bytes.map(|b| b.to_string()).unwrap_or_default()
+        )?;
+    }
+    Ok(())
+}
+
+/// Like [`write`], to a new file at `path`.
+pub fn write_file(
+    path: &Path,
+    client: &str,
+    times: &[Duration],
+    row_counts: &[usize],
+    phases: &[Phases],
+) -> Result<()> {
+    let file =
+        File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    let mut handle = BufWriter::new(file);
+    write(client, times, row_counts, phases, &mut handle)?;
+    handle
+        .flush()
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_samples() {
+        let ms = Duration::from_millis;
+        let phases = [Phases {
+            connect: Some(ms(120)),
+            execute: Some(Duration::from_micros(45_250)),
+            first_batch: Some(ms(10)),
+            drain: Some(ms(2)),
+            bytes: Some(4096),
+        }];
+        let mut out = Vec::new();
+        write("adbc", &[ms(178), ms(60)], &[10, 10], &phases, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "iteration,client,total_ms,connect_ms,execute_ms,first_batch_ms,drain_ms,rows,bytes\n\
+             1,adbc,178.000,120.000,45.250,10.000,2.000,10,4096\n\
+             2,adbc,60.000,,,,,10,\n"
+        );
+    }
+}
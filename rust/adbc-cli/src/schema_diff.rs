@@ -0,0 +1,117 @@
+//! Comparing the columns of two tables, e.g. the same table in dev and prod,
+//! for `schema-diff`.
+
+use std::io::Write;
+
+use anyhow::Result;
+use arrow_schema::{Field, Schema};
+
+/// How a column differs between the two tables.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ColumnChange<'a> {
+    /// Only the right table has the column.
+    Added(&'a Field),
+    /// Only the left table has the column.
+    Removed(&'a Field),
+    /// Both tables have the column, with a different type or nullability.
+    Changed { left: &'a Field, right: &'a Field },
+}
+
+/// Compare columns by name, in the left table's order followed by columns
+/// only the right table has. Column order is otherwise ignored.
+pub fn compare<'a>(left: &'a Schema, right: &'a Schema) -> Vec<ColumnChange<'a>> {
+    let mut changes = Vec::new();
+    for l in left.fields() {
+        match right.field_with_name(l.name()) {
+            Err(_) => changes.push(ColumnChange::Removed(l)),
+            Ok(r) if l.data_type() != r.data_type() || l.is_nullable() != r.is_nullable() => {
+                changes.push(ColumnChange::Changed { left: l, right: r })
+            }
+            Ok(_) => {}
+        }
+    }
+    for r in right.fields() {
+        if left.field_with_name(r.name()).is_err() {
+            changes.push(ColumnChange::Added(r));
+        }
+    }
+    changes
+}
+
+fn describe(field: &Field) -> String {
+    format!(
+        "{} {}{}",
+        field.name(),
+        field.data_type(),
+        if field.is_nullable() { "" } else { " NOT NULL" }
+    )
+}
+
+/// Write `changes` as a unified diff: `-` for removed columns, `+` for added
+/// ones, and a `-`/`+` pair for changed ones.
+pub fn write_report(
+    changes: &[ColumnChange],
+    left_name: &str,
+    right_name: &str,
+    handle: &mut dyn Write,
+) -> Result<()> {
+    if changes.is_empty() {
+        writeln!(handle, "Schemas match.")?;
+        return Ok(());
+    }
+    writeln!(handle, "--- {}", left_name)?;
+    writeln!(handle, "+++ {}", right_name)?;
+    for change in changes {
+        match change {
+            ColumnChange::Added(field) => writeln!(handle, "+ {}", describe(field))?,
+            ColumnChange::Removed(field) => writeln!(handle, "- {}", describe(field))?,
+            ColumnChange::Changed { left, right } => {
+                writeln!(handle, "- {}", describe(left))?;
+                writeln!(handle, "+ {}", describe(right))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_schema::DataType;
+
+    use super::*;
+
+    #[test]
+    fn compare_columns() {
+        let left = Schema::new(vec![
+            Field::new("ID", DataType::Int64, false),
+            Field::new("NAME", DataType::Utf8, true),
+            Field::new("LEGACY", DataType::Utf8, true),
+            Field::new("TOTAL", DataType::Float64, true),
+        ]);
+        let right = Schema::new(vec![
+            Field::new("NAME", DataType::Utf8, true),
+            Field::new("ID", DataType::Int64, false),
+            Field::new("TOTAL", DataType::Decimal128(38, 2), false),
+            Field::new("CREATED_AT", DataType::Utf8, true),
+        ]);
+        let changes = compare(&left, &right);
+        assert_eq!(changes.len(), 3);
+
+        let mut out = Vec::new();
+        write_report(&changes, "dev:T", "prod:T", &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "--- dev:T\n\
+             +++ prod:T\n\
+             - LEGACY Utf8\n\
+             - TOTAL Float64\n\
+             + TOTAL Decimal128(38, 2) NOT NULL\n\
+             + CREATED_AT Utf8\n"
+        );
+
+        assert!(compare(&left, &left).is_empty());
+        let mut out = Vec::new();
+        write_report(&[], "a", "b", &mut out).unwrap();
+        assert_eq!(out, b"Schemas match.\n");
+    }
+}
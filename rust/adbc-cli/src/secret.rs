@@ -0,0 +1,102 @@
+//! Reading secrets from a command for `password_command` and
+//! `private_key_command`, like git's credential helpers, so a secrets manager
+//! can supply them through a small wrapper script instead of the config file
+//! or the environment.
+//!
+//! The command's stdout is the secret, so it never appears in errors or logs;
+//! its stderr goes to the terminal, for prompts and diagnostics.
+
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result};
+
+fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    }
+}
+
+/// Run `command` with the shell and return its stdout with surrounding
+/// whitespace trimmed. `field` names the profile field it came from, for
+/// errors, which never include the output.
+pub fn from_command(field: &str, command: &str) -> Result<String> {
+    let output = shell(command)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()
+        .with_context(|| format!("Failed to run {}", field))?;
+    if !output.status.success() {
+        anyhow::bail!("{} failed ({})", field, output.status);
+    }
+    let secret = String::from_utf8(output.stdout)
+        .map_err(|_| anyhow::anyhow!("{} printed invalid UTF-8", field))?;
+    let secret = secret.trim();
+    if secret.is_empty() {
+        anyhow::bail!("{} printed nothing", field);
+    }
+    Ok(secret.to_string())
+}
+
+/// A secret set either directly in the profile or by a command there.
+pub fn resolve(
+    field: &str,
+    value: Option<&String>,
+    command: Option<&String>,
+) -> Result<Option<String>> {
+    match (value, command) {
+        (Some(_), Some(_)) => {
+            anyhow::bail!(
+                "The profile sets both {0} and {0}_command; remove one",
+                field
+            )
+        }
+        (Some(value), None) => Ok(Some(value.clone())),
+        (None, Some(command)) => from_command(&format!("{}_command", field), command).map(Some),
+        (None, None) => Ok(None),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_secret_from_command() {
+        assert_eq!(
+            from_command("password_command", "printf '  s3cret\\n'").unwrap(),
+            "s3cret"
+        );
+
+        let err = from_command("password_command", "echo leaked; exit 3").unwrap_err();
+        let message = format!("{:#}", err);
+        assert!(
+            message.starts_with("password_command failed"),
+            "{}",
+            message
+        );
+        assert!(!message.contains("leaked"), "{}", message);
+
+        assert_eq!(
+            from_command("private_key_command", "true")
+                .unwrap_err()
+                .to_string(),
+            "private_key_command printed nothing"
+        );
+
+        let command = "echo from-command".to_string();
+        assert_eq!(
+            resolve("password", None, Some(&command))
+                .unwrap()
+                .as_deref(),
+            Some("from-command")
+        );
+        assert!(resolve("password", Some(&"x".to_string()), Some(&command)).is_err());
+        assert_eq!(resolve("password", None, None).unwrap(), None);
+    }
+}
@@ -0,0 +1,239 @@
+//! Sending results to an HTTP endpoint with `--sink`, for piping rows into
+//! another service without an intermediate file.
+//!
+//! Rows are POSTed as JSON arrays of objects, `--sink-batch-rows` rows per
+//! request. Requests are sent one at a time and the next batch isn't fetched
+//! until the endpoint has accepted the last, so a slow endpoint slows the
+//! export rather than buffering it in memory.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use arrow_array::{RecordBatch, RecordBatchIterator, RecordBatchReader};
+use reqwest::header::{CONTENT_TYPE, RETRY_AFTER};
+use reqwest::StatusCode;
+use url::Url;
+
+use crate::output::{self, OutputFormat, RenderOptions};
+
+/// The longest wait between retries.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Where and how to send rows.
+#[derive(Clone, Debug)]
+pub struct HttpSink {
+    pub url: Url,
+    /// Rows per request.
+    pub batch_rows: usize,
+    /// Retries of a request the endpoint rejects as overloaded or that fails
+    /// to reach it.
+    pub retries: u32,
+}
+
+/// Parse a `--sink` URL, which must be `http://` or `https://`.
+pub fn parse_url(s: &str) -> Result<Url, String> {
+    let url = Url::parse(s).map_err(|e| format!("invalid URL '{}': {}", s, e))?;
+    match url.scheme() {
+        "http" | "https" => Ok(url),
+        scheme => Err(format!(
+            "unsupported sink scheme '{}'; only http:// and https:// are supported",
+            scheme
+        )),
+    }
+}
+
+/// Accumulates batches into payloads of a fixed number of rows.
+struct Payloads {
+    pending: VecDeque<RecordBatch>,
+    pending_rows: usize,
+    rows: usize,
+}
+
+impl Payloads {
+    fn new(rows: usize) -> Self {
+        Self {
+            pending: VecDeque::new(),
+            pending_rows: 0,
+            rows: rows.max(1),
+        }
+    }
+
+    fn push(&mut self, batch: RecordBatch) {
+        self.pending_rows += batch.num_rows();
+        self.pending.push_back(batch);
+    }
+
+    /// The next full payload, or with `finish`, the rows that remain.
+    fn next(&mut self, finish: bool) -> Option<Vec<RecordBatch>> {
+        if self.pending_rows == 0 || (!finish && self.pending_rows < self.rows) {
+            return None;
+        }
+        let mut payload = Vec::new();
+        let mut needed = self.rows;
+        while needed > 0 {
+            let Some(batch) = self.pending.pop_front() else {
+                break;
+            };
+            if batch.num_rows() > needed {
+                self.pending
+                    .push_front(batch.slice(needed, batch.num_rows() - needed));
+                payload.push(batch.slice(0, needed));
+                needed = 0;
+            } else {
+                needed -= batch.num_rows();
+                payload.push(batch);
+            }
+        }
+        self.pending_rows -= self.rows - needed;
+        Some(payload)
+    }
+}
+
+/// Returns true if a request rejected with `status` may succeed if retried.
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::REQUEST_TIMEOUT
+        || status.is_server_error()
+}
+
+/// Exponential backoff for the `attempt`th retry: 0.5s, 1s, 2s, ... up to
+/// [`MAX_BACKOFF`].
+fn backoff(attempt: u32) -> Duration {
+    Duration::from_millis(500)
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(MAX_BACKOFF)
+}
+
+/// POST `body` to the sink, retrying while the endpoint is unreachable or
+/// overloaded. A `Retry-After` header in seconds overrides the backoff.
+async fn post(client: &reqwest::Client, sink: &HttpSink, body: Vec<u8>) -> Result<()> {
+    let mut attempt = 0;
+    loop {
+        let response = client
+            .post(sink.url.clone())
+            .header(CONTENT_TYPE, "application/json")
+            .body(body.clone())
+            .send()
+            .await;
+        let (error, retry_after) = match response {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) if is_retryable(response.status()) => {
+                let retry_after = response
+                    .headers()
+                    .get(RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok()?.parse().ok())
+                    .map(Duration::from_secs);
+                (
+                    anyhow::anyhow!("{} responded {}", sink.url, response.status()),
+                    retry_after,
+                )
+            }
+            Ok(response) => {
+                let status = response.status();
+                let text = response.text().await.unwrap_or_default();
+                anyhow::bail!("{} rejected the rows: {} {}", sink.url, status, text.trim());
+            }
+            Err(e) => (
+                anyhow::Error::new(e).context(format!("Failed to send rows to {}", sink.url)),
+                None,
+            ),
+        };
+        if attempt >= sink.retries {
+            return Err(error.context(format!("Giving up after {} retries", attempt)));
+        }
+        attempt += 1;
+        let delay = retry_after
+            .unwrap_or_else(|| backoff(attempt))
+            .min(MAX_BACKOFF);
+        eprintln!("{:#}; retrying in {:.1?}.", error, delay);
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Send the rows of `reader` to `sink`, returning the number of rows sent.
+pub fn send(
+    reader: impl RecordBatchReader + Send,
+    options: &RenderOptions,
+    sink: &HttpSink,
+) -> Result<usize> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(60))
+        .build()
+        .context("Failed to create HTTP client")?;
+    let runtime = tokio::runtime::Handle::current();
+    let schema = reader.schema();
+    let mut payloads = Payloads::new(sink.batch_rows);
+    let (mut rows, mut requests) = (0, 0);
+    let mut batches = reader.into_iter();
+    loop {
+        let batch = batches.next().transpose()?;
+        let finish = batch.is_none();
+        if let Some(batch) = batch {
+            payloads.push(batch);
+        }
+        while let Some(payload) = payloads.next(finish) {
+            let payload_rows: usize = payload.iter().map(RecordBatch::num_rows).sum();
+            let mut body = Vec::new();
+            output::write_output(
+                RecordBatchIterator::new(payload.into_iter().map(Ok), schema.clone()),
+                OutputFormat::Json,
+                options,
+                &mut body,
+            )?;
+            // Results are written synchronously from within the async runtime.
+            tokio::task::block_in_place(|| runtime.block_on(post(&client, sink, body)))?;
+            rows += payload_rows;
+            requests += 1;
+        }
+        if finish {
+            break;
+        }
+    }
+    eprintln!(
+        "Sent {} rows in {} requests to {}",
+        rows, requests, sink.url
+    );
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::Int64Array;
+    use arrow_schema::{DataType, Field, Schema};
+
+    use super::*;
+
+    #[test]
+    fn split_into_payloads() {
+        let schema = Arc::new(Schema::new(vec![Field::new("ID", DataType::Int64, false)]));
+        let batch = |ids: Vec<i64>| {
+            RecordBatch::try_new(schema.clone(), vec![Arc::new(Int64Array::from(ids))]).unwrap()
+        };
+        let sizes = |payload: Vec<RecordBatch>| -> Vec<usize> {
+            payload.iter().map(RecordBatch::num_rows).collect()
+        };
+
+        let mut payloads = Payloads::new(3);
+        payloads.push(batch(vec![1, 2]));
+        assert!(payloads.next(false).is_none());
+        payloads.push(batch(vec![3, 4, 5, 6, 7]));
+        assert_eq!(sizes(payloads.next(false).unwrap()), [2, 1]);
+        assert_eq!(sizes(payloads.next(false).unwrap()), [3]);
+        assert!(payloads.next(false).is_none());
+        assert_eq!(sizes(payloads.next(true).unwrap()), [1]);
+        assert!(payloads.next(true).is_none());
+
+        assert!(is_retryable(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable(StatusCode::BAD_GATEWAY));
+        assert!(!is_retryable(StatusCode::BAD_REQUEST));
+        assert_eq!(backoff(1), Duration::from_millis(500));
+        assert_eq!(backoff(3), Duration::from_secs(2));
+        assert_eq!(backoff(20), MAX_BACKOFF);
+
+        assert!(parse_url("https://example.com/ingest").is_ok());
+        assert!(parse_url("kafka://broker/topic").is_err());
+    }
+}
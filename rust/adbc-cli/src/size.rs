@@ -0,0 +1,69 @@
+//! Sizes in bytes, given on the command line as e.g. `500MB` or `2GB`.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// A number of bytes, parsed from e.g. `500MB` or `2GB` (binary multiples)
+/// and displayed as e.g. `2.1 GB`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ByteSize(pub u64);
+
+const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB", "PB"];
+
+impl FromStr for ByteSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let number: f64 = number
+            .parse()
+            .map_err(|_| format!("expected a size such as 500MB or 2GB, got '{}'", s))?;
+        let unit = unit.trim().to_ascii_uppercase();
+        let exponent = match unit.as_str() {
+            "" => 0,
+            _ => UNITS
+                .iter()
+                .position(|u| *u == unit || u.trim_end_matches('B') == unit)
+                .ok_or_else(|| format!("unknown size unit '{}'", unit))?,
+        };
+        Ok(ByteSize((number * 1024f64.powi(exponent as i32)) as u64))
+    }
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut value = self.0 as f64;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+        if unit == 0 {
+            write!(f, "{} B", self.0)
+        } else {
+            write!(f, "{:.1} {}", value, UNITS[unit])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn byte_sizes() {
+        assert_eq!("500".parse(), Ok(ByteSize(500)));
+        assert_eq!("2KB".parse(), Ok(ByteSize(2048)));
+        assert_eq!("1.5 gb".parse(), Ok(ByteSize(3 << 29)));
+        assert_eq!("10G".parse(), Ok(ByteSize(10 << 30)));
+        assert!("GB".parse::<ByteSize>().is_err());
+        assert!("10XB".parse::<ByteSize>().is_err());
+
+        assert_eq!(ByteSize(512).to_string(), "512 B");
+        assert_eq!(ByteSize(2_254_857_830).to_string(), "2.1 GB");
+    }
+}
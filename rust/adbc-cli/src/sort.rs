@@ -0,0 +1,127 @@
+//! Client-side sorting of a fetched result, for exploring views or queries
+//! without editing their SQL to add `ORDER BY`.
+
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use arrow_array::RecordBatch;
+use arrow_ord::sort::{lexsort_to_indices, SortColumn, SortOptions};
+use arrow_schema::SchemaRef;
+use arrow_select::concat::concat_batches;
+use arrow_select::take::take_record_batch;
+
+/// A column to sort by, given as `column` or `column:desc`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SortKey {
+    column: String,
+    descending: bool,
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let (column, descending) = match spec.rsplit_once(':') {
+            Some((column, dir)) if dir.eq_ignore_ascii_case("desc") => (column, true),
+            Some((column, dir)) if dir.eq_ignore_ascii_case("asc") => (column, false),
+            Some((_, dir)) => return Err(format!("expected asc or desc, got '{}'", dir)),
+            None => (spec, false),
+        };
+        if column.is_empty() {
+            return Err("empty column name".to_string());
+        }
+        Ok(SortKey {
+            column: column.to_string(),
+            descending,
+        })
+    }
+}
+
+/// Sort `batches` by `keys` into a single batch. As in Snowflake, NULLs sort
+/// last in ascending order and first in descending order.
+pub fn sort(schema: &SchemaRef, batches: &[RecordBatch], keys: &[SortKey]) -> Result<RecordBatch> {
+    let batch = concat_batches(schema, batches)?;
+    let columns = keys
+        .iter()
+        .map(|key| {
+            // Snowflake upper-cases unquoted names, so fall back to a
+            // case-insensitive match.
+            let idx = schema
+                .index_of(&key.column)
+                .ok()
+                .or_else(|| {
+                    schema
+                        .fields()
+                        .iter()
+                        .position(|f| f.name().eq_ignore_ascii_case(&key.column))
+                })
+                .with_context(|| format!("--order-by: no column named {}", key.column))?;
+            Ok(SortColumn {
+                values: batch.column(idx).clone(),
+                options: Some(SortOptions {
+                    descending: key.descending,
+                    nulls_first: key.descending,
+                }),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let indices = lexsort_to_indices(&columns, None)?;
+    Ok(take_record_batch(&batch, &indices)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::Int64Type;
+    use arrow_array::{Int64Array, StringArray};
+    use arrow_schema::{DataType, Field, Schema};
+
+    use super::*;
+
+    #[test]
+    fn sort_by_keys() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("GROUP", DataType::Utf8, true),
+            Field::new("N", DataType::Int64, true),
+        ]));
+        let batch = |groups: Vec<&str>, ns: Vec<Option<i64>>| {
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(StringArray::from(groups)),
+                    Arc::new(Int64Array::from(ns)),
+                ],
+            )
+            .unwrap()
+        };
+        let batches = [
+            batch(vec!["b", "a"], vec![Some(1), Some(2)]),
+            batch(vec!["a", "b"], vec![None, Some(3)]),
+        ];
+        let keys: Vec<SortKey> = ["group", "N:desc"]
+            .iter()
+            .map(|k| k.parse().unwrap())
+            .collect();
+
+        let sorted = sort(&schema, &batches, &keys).unwrap();
+        let groups: Vec<&str> = sorted
+            .column(0)
+            .as_string::<i32>()
+            .iter()
+            .flatten()
+            .collect();
+        assert_eq!(groups, ["a", "a", "b", "b"]);
+        let ns: Vec<Option<i64>> = sorted
+            .column(1)
+            .as_primitive::<Int64Type>()
+            .iter()
+            .collect();
+        assert_eq!(ns, [None, Some(2), Some(3), Some(1)]);
+
+        assert!("N:sideways".parse::<SortKey>().is_err());
+        let missing: SortKey = "X".parse().unwrap();
+        assert!(sort(&schema, &batches, &[missing]).is_err());
+    }
+}
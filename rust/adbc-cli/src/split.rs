@@ -0,0 +1,374 @@
+//! Bounding the size of output files: `--max-file-size` fails an export that
+//! outgrows the limit, or with `--split` rolls over to numbered files
+//! (`out.1.csv`, `out.2.csv`, ...) instead. `--output-file-pattern` writes
+//! every K batches to a file of their own, for consumers that read files in
+//! parallel.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use arrow_array::{RecordBatch, RecordBatchReader};
+use arrow_schema::{ArrowError, SchemaRef};
+
+use crate::output::{self, OutputFormat, RenderOptions};
+use crate::size::ByteSize;
+
+/// Wraps a writer, failing any write that would take it past `max` bytes.
+pub struct Limited<W> {
+    inner: W,
+    written: u64,
+    max: Option<u64>,
+}
+
+impl<W: Write> Limited<W> {
+    pub fn new(inner: W, max: Option<ByteSize>) -> Self {
+        Self {
+            inner,
+            written: 0,
+            max: max.map(|max| max.0),
+        }
+    }
+}
+
+impl<W: Write> Write for Limited<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some(max) = self.max {
+            if self.written + buf.len() as u64 > max {
+                return Err(io::Error::other(format!(
+                    "output file reached --max-file-size {}; pass --split to continue in \
+                     further files",
+                    ByteSize(max)
+                )));
+            }
+        }
+        let written = self.inner.write(buf)?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Where `--split` writes its parts, and the size at which each is closed.
+#[derive(Clone, Debug)]
+pub struct SplitOutput {
+    pub path: PathBuf,
+    pub max: ByteSize,
+    /// The number of the next part, continuing across statements.
+    next: Arc<AtomicUsize>,
+}
+
+impl SplitOutput {
+    pub fn new(path: PathBuf, max: ByteSize) -> Self {
+        Self {
+            path,
+            max,
+            next: Arc::new(AtomicUsize::new(1)),
+        }
+    }
+}
+
+/// The path of part `n` of a split file: `out.csv` becomes `out.<n>.csv`.
+fn part_path(path: &Path, n: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{}.{}.{}", stem, n, extension.to_string_lossy()),
+        None => format!("{}.{}", stem, n),
+    };
+    path.with_file_name(name)
+}
+
+/// Counts the bytes written through it.
+struct Counting<W> {
+    inner: W,
+    written: Arc<AtomicU64>,
+}
+
+impl<W: Write> Write for Counting<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.written.fetch_add(written as u64, Ordering::Relaxed);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// The placeholder replaced by the file number in `--output-file-pattern`.
+const NUMBER_PLACEHOLDER: &str = "{n}";
+
+/// A file name pattern such as `part-{n}.parquet`, numbered from 1.
+#[derive(Clone, Debug)]
+pub struct OutputPattern {
+    pattern: String,
+    /// The number of the next file, continuing across statements.
+    next: Arc<AtomicUsize>,
+}
+
+impl FromStr for OutputPattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.contains(NUMBER_PLACEHOLDER) {
+            return Err(format!(
+                "expected a pattern containing {}, e.g. part-{}.parquet",
+                NUMBER_PLACEHOLDER, NUMBER_PLACEHOLDER
+            ));
+        }
+        Ok(Self {
+            pattern: s.to_string(),
+            next: Arc::new(AtomicUsize::new(1)),
+        })
+    }
+}
+
+impl OutputPattern {
+    /// The pattern as a path, to infer the output format from.
+    pub fn as_path(&self) -> &Path {
+        Path::new(&self.pattern)
+    }
+
+    fn path(&self, n: usize) -> PathBuf {
+        PathBuf::from(self.pattern.replace(NUMBER_PLACEHOLDER, &n.to_string()))
+    }
+}
+
+/// When a part is closed.
+#[derive(Clone, Copy)]
+enum PartLimit {
+    /// Once its file reaches this many bytes.
+    Bytes(u64),
+    /// After this many batches.
+    Batches(usize),
+}
+
+/// The batches of one part: those of the source reader until the part
+/// reaches its limit.
+struct Part<'a, R: Iterator> {
+    source: &'a mut Peekable<R>,
+    schema: SchemaRef,
+    written: Arc<AtomicU64>,
+    batches: usize,
+    limit: PartLimit,
+}
+
+impl<R: Iterator<Item = Result<RecordBatch, ArrowError>>> Iterator for Part<'_, R> {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let full = match self.limit {
+            PartLimit::Bytes(max) => self.written.load(Ordering::Relaxed) >= max,
+            PartLimit::Batches(max) => self.batches >= max,
+        };
+        if full {
+            return None;
+        }
+        self.batches += 1;
+        self.source.next()
+    }
+}
+
+impl<R: Iterator<Item = Result<RecordBatch, ArrowError>>> RecordBatchReader for Part<'_, R> {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+/// Write the batches of `reader` to numbered files, starting a new file once
+/// one reaches the limit. Files are split between batches, so each can exceed
+/// the limit by up to a batch, and each is complete in itself (e.g. every CSV
+/// part has a header).
+pub fn write_parts(
+    reader: impl RecordBatchReader + Send,
+    format: OutputFormat,
+    options: &RenderOptions,
+    split: &SplitOutput,
+) -> Result<()> {
+    write_numbered(
+        reader,
+        format,
+        options,
+        || part_path(&split.path, split.next.fetch_add(1, Ordering::Relaxed)),
+        PartLimit::Bytes(split.max.0),
+    )
+}
+
+/// Write every `batches_per_file` batches of `reader` to the next file named
+/// by `pattern`, closing each file before opening the next.
+pub fn write_pattern(
+    reader: impl RecordBatchReader + Send,
+    format: OutputFormat,
+    options: &RenderOptions,
+    pattern: &OutputPattern,
+    batches_per_file: usize,
+) -> Result<()> {
+    write_numbered(
+        reader,
+        format,
+        options,
+        || pattern.path(pattern.next.fetch_add(1, Ordering::Relaxed)),
+        PartLimit::Batches(batches_per_file),
+    )
+}
+
+/// Write the batches of `reader` to a file for each part, named by
+/// `next_path`. At least one file is written, so an empty result still has
+/// its schema written out.
+fn write_numbered(
+    reader: impl RecordBatchReader + Send,
+    format: OutputFormat,
+    options: &RenderOptions,
+    mut next_path: impl FnMut() -> PathBuf,
+    limit: PartLimit,
+) -> Result<()> {
+    let schema = reader.schema();
+    let mut source = reader.peekable();
+    let mut first = true;
+    while first || source.peek().is_some() {
+        first = false;
+        let path = next_path();
+        let file =
+            File::create(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+        let written = Arc::new(AtomicU64::new(0));
+        let mut writer = Counting {
+            inner: BufWriter::new(file),
+            written: written.clone(),
+        };
+        let part = Part {
+            source: &mut source,
+            schema: schema.clone(),
+            written,
+            batches: 0,
+            limit,
+        };
+        output::write_output(part, format, options, &mut writer)?;
+        writer.flush()?;
+        println!("Wrote {}", path.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::{Int64Array, RecordBatchIterator};
+    use arrow_schema::{DataType, Field, Schema};
+
+    use super::*;
+
+    #[test]
+    fn limit_writes() {
+        let mut out = Limited::new(Vec::new(), Some(ByteSize(8)));
+        out.write_all(b"12345").unwrap();
+        let err = out.write_all(b"6789").unwrap_err();
+        assert!(err.to_string().contains("--max-file-size 8 B"));
+        assert_eq!(out.inner, b"12345");
+
+        let mut out = Limited::new(Vec::new(), None);
+        out.write_all(&[0; 100]).unwrap();
+    }
+
+    #[test]
+    fn split_into_parts() {
+        assert_eq!(
+            part_path(Path::new("exports/out.csv"), 2),
+            Path::new("exports/out.2.csv")
+        );
+        assert_eq!(part_path(Path::new("out"), 1), Path::new("out.1"));
+
+        let dir = std::env::temp_dir().join(format!("adbc-cli-split-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let schema = Arc::new(Schema::new(vec![Field::new("ID", DataType::Int64, false)]));
+        let batches: Vec<_> = (0..5)
+            .map(|idx| {
+                RecordBatch::try_new(
+                    schema.clone(),
+                    vec![Arc::new(Int64Array::from_iter_values(
+                        idx * 10..idx * 10 + 10,
+                    ))],
+                )
+            })
+            .collect();
+        let split = SplitOutput::new(dir.join("out.csv"), ByteSize(40));
+        write_parts(
+            RecordBatchIterator::new(batches, schema.clone()),
+            OutputFormat::Csv,
+            &RenderOptions::default(),
+            &split,
+        )
+        .unwrap();
+
+        // Each part starts with a 3-byte header and each batch adds 20-30
+        // bytes, so parts close after two batches.
+        let part = |n| std::fs::read_to_string(part_path(&split.path, n)).unwrap();
+        assert!(part(1).starts_with("ID\n0\n"));
+        assert!(part(2).starts_with("ID\n20\n"));
+        assert!(part(3).starts_with("ID\n40\n"));
+
+        // Numbering continues with the next result.
+        let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(Int64Array::from(vec![7]))]);
+        write_parts(
+            RecordBatchIterator::new([batch], schema),
+            OutputFormat::Csv,
+            &RenderOptions::default(),
+            &split,
+        )
+        .unwrap();
+        assert_eq!(part(4), "ID\n7\n");
+        assert!(!part_path(&split.path, 5).exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn write_batches_by_pattern() {
+        assert!("part.parquet".parse::<OutputPattern>().is_err());
+
+        let dir = std::env::temp_dir().join(format!("adbc-cli-pattern-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let pattern: OutputPattern = dir.join("part-{n}.csv").to_str().unwrap().parse().unwrap();
+        let schema = Arc::new(Schema::new(vec![Field::new("ID", DataType::Int64, false)]));
+        let batch = |ids: Vec<i64>| {
+            RecordBatch::try_new(schema.clone(), vec![Arc::new(Int64Array::from(ids))])
+        };
+        let reader = RecordBatchIterator::new(
+            vec![batch(vec![1]), batch(vec![2]), batch(vec![3])],
+            schema.clone(),
+        );
+        write_pattern(
+            reader,
+            OutputFormat::Csv,
+            &RenderOptions::default(),
+            &pattern,
+            2,
+        )
+        .unwrap();
+        // Numbering continues with the next result.
+        let reader = RecordBatchIterator::new(vec![batch(vec![4])], schema.clone());
+        write_pattern(
+            reader,
+            OutputFormat::Csv,
+            &RenderOptions::default(),
+            &pattern,
+            2,
+        )
+        .unwrap();
+
+        let part = |n| std::fs::read_to_string(pattern.path(n)).unwrap();
+        assert_eq!(part(1), "ID\n1\n2\n");
+        assert_eq!(part(2), "ID\n3\n");
+        assert_eq!(part(3), "ID\n4\n");
+        assert!(!pattern.path(4).exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
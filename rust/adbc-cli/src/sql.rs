@@ -0,0 +1,313 @@
+//! Lightweight helpers for inspecting SQL text without a full parser.
+
+/// Statements that modify data or schema and report an affected-row count
+/// instead of returning rows. `COPY`, `PUT`, and `REMOVE` aren't among them:
+/// they return a status row per file, which is worth showing.
+const UPDATE_KEYWORDS: &[&str] = &[
+    "INSERT", "UPDATE", "DELETE", "MERGE", "CREATE", "DROP", "ALTER", "TRUNCATE", "GRANT",
+    "REVOKE", "UNDROP", "COMMENT",
+];
+
+/// Statements that only read data or change session context, allowed in
+/// `--read-only` mode. Anything else (including `CALL`, since a procedure may
+/// write) is refused.
+const READ_ONLY_KEYWORDS: &[&str] = &[
+    "SELECT", "WITH", "SHOW", "DESCRIBE", "DESC", "EXPLAIN", "LIST", "LS", "USE", "SET", "UNSET",
+    "VALUES",
+];
+
+/// Returns `sql` without leading whitespace, `--` line comments, and `/* */`
+/// block comments.
+fn skip_comments(sql: &str) -> &str {
+    let mut rest = sql;
+    loop {
+        rest = rest.trim_start();
+        if let Some(comment) = rest.strip_prefix("--") {
+            rest = comment.split_once('\n').map_or("", |(_, after)| after);
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            rest = comment.split_once("*/").map_or("", |(_, after)| after);
+        } else {
+            return rest;
+        }
+    }
+}
+
+/// Returns the first keyword of `sql`, upper-cased, skipping leading
+/// whitespace, `--` line comments, and `/* */` block comments.
+pub fn first_keyword(sql: &str) -> Option<String> {
+    let keyword: String = skip_comments(sql)
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic() || *c == '_')
+        .collect();
+    (!keyword.is_empty()).then(|| keyword.to_ascii_uppercase())
+}
+
+/// Split a script into statements on `;`, ignoring semicolons inside string
+/// literals (where `\` escapes the next character, as in Snowflake), quoted
+/// identifiers, `$$`-delimited bodies, and comments.
+/// Statements that contain nothing but whitespace and comments are dropped.
+pub fn split_statements(script: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut chars = script.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        current.push(c);
+        match c {
+            '\'' | '"' => {
+                while let Some(next) = chars.next() {
+                    current.push(next);
+                    if next == '\\' && c == '\'' {
+                        current.extend(chars.next());
+                    } else if next == c {
+                        break;
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'$') => {
+                current.push(chars.next().unwrap());
+                while let Some(next) = chars.next() {
+                    current.push(next);
+                    if next == '$' && chars.peek() == Some(&'$') {
+                        current.push(chars.next().unwrap());
+                        break;
+                    }
+                }
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                for next in chars.by_ref() {
+                    current.push(next);
+                    if next == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                current.push(chars.next().unwrap());
+                while let Some(next) = chars.next() {
+                    current.push(next);
+                    if next == '*' && chars.peek() == Some(&'/') {
+                        current.push(chars.next().unwrap());
+                        break;
+                    }
+                }
+            }
+            ';' => {
+                current.pop();
+                push_statement(&mut statements, &current);
+                current.clear();
+            }
+            _ => {}
+        }
+    }
+    push_statement(&mut statements, &current);
+
+    statements
+}
+
+fn push_statement(statements: &mut Vec<String>, statement: &str) {
+    if !skip_comments(statement).is_empty() {
+        statements.push(statement.trim().to_string());
+    }
+}
+
+/// Returns the name given to a statement by a leading `-- name: <name>`
+/// comment, restricted to characters that are safe in file names.
+pub fn statement_name(sql: &str) -> Option<String> {
+    sql.lines()
+        .map(str::trim)
+        .take_while(|line| line.is_empty() || line.starts_with("--"))
+        .find_map(|line| line.trim_start_matches('-').trim().strip_prefix("name:"))
+        .map(|name| {
+            name.trim()
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect::<String>()
+        })
+        .filter(|name| !name.is_empty())
+}
+
+/// Returns true if `sql` is a DML/DDL statement that should be run with
+/// `execute_update`.
+pub fn is_update_statement(sql: &str) -> bool {
+    first_keyword(sql).is_some_and(|keyword| UPDATE_KEYWORDS.contains(&keyword.as_str()))
+}
+
+/// Returns true if `sql` can't modify data or schema, judged by its first
+/// keyword.
+pub fn is_read_only(sql: &str) -> bool {
+    first_keyword(sql).is_some_and(|keyword| READ_ONLY_KEYWORDS.contains(&keyword.as_str()))
+}
+
+/// The secondary roles of a session: `ALL`, `NONE`, or a list of roles.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SecondaryRoles {
+    All,
+    None,
+    Roles(Vec<String>),
+}
+
+impl std::str::FromStr for SecondaryRoles {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("all") {
+            return Ok(SecondaryRoles::All);
+        }
+        if s.eq_ignore_ascii_case("none") {
+            return Ok(SecondaryRoles::None);
+        }
+        let roles: Vec<String> = s.split(',').map(|role| role.trim().to_string()).collect();
+        match roles.iter().find(|role| !is_identifier(role)) {
+            Some(role) => Err(format!(
+                "expected ALL, NONE, or a comma-separated list of roles, got '{}'",
+                if role.is_empty() { s } else { role }
+            )),
+            None => Ok(SecondaryRoles::Roles(roles)),
+        }
+    }
+}
+
+impl SecondaryRoles {
+    /// The statement activating these secondary roles.
+    pub fn to_sql(&self) -> String {
+        match self {
+            SecondaryRoles::All => "USE SECONDARY ROLES ALL".to_string(),
+            SecondaryRoles::None => "USE SECONDARY ROLES NONE".to_string(),
+            SecondaryRoles::Roles(roles) => format!("USE SECONDARY ROLES {}", roles.join(", ")),
+        }
+    }
+}
+
+/// Returns true if `name` is an unquoted identifier, or a double-quoted one.
+pub fn is_identifier(name: &str) -> bool {
+    if let Some(quoted) = name
+        .strip_prefix('"')
+        .and_then(|name| name.strip_suffix('"'))
+    {
+        return !quoted.is_empty() && !quoted.replace("\"\"", "").contains('"');
+    }
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+/// Returns true if `name` is an identifier qualified by up to a database and
+/// schema, e.g. `DB.PUBLIC.ORDERS`.
+pub fn is_table_name(name: &str) -> bool {
+    let parts: Vec<&str> = name.split('.').collect();
+    parts.len() <= 3 && parts.iter().all(|part| is_identifier(part))
+}
+
+/// `name` as an identifier in SQL: as it is if it's already a valid unquoted
+/// or quoted identifier, so Snowflake still upper-cases unquoted names, and
+/// otherwise double-quoted, with its quotes escaped.
+pub fn identifier(name: &str) -> String {
+    if is_identifier(name) {
+        name.to_string()
+    } else {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    }
+}
+
+/// Quote `value` as a SQL string literal, escaping quotes and, as Snowflake
+/// treats it as an escape character, backslashes.
+pub fn string_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_keyword_skips_comments() {
+        assert_eq!(first_keyword("select 1").as_deref(), Some("SELECT"));
+        assert_eq!(
+            first_keyword("  -- note\n/* block */ insert into t values (1)").as_deref(),
+            Some("INSERT")
+        );
+        assert_eq!(first_keyword("-- only a comment"), None);
+        assert_eq!(first_keyword(""), None);
+    }
+
+    #[test]
+    fn update_statements() {
+        assert!(is_update_statement("INSERT INTO t VALUES (1)"));
+        assert!(is_update_statement("create table t (a int)"));
+        assert!(!is_update_statement("SELECT * FROM t"));
+        assert!(!is_update_statement("WITH x AS (SELECT 1) SELECT * FROM x"));
+        assert!(!is_update_statement("SHOW TABLES"));
+        assert!(!is_update_statement("COPY INTO t FROM @stage"));
+        assert!(!is_update_statement("PUT file:///tmp/data.csv @stage"));
+        assert!(!is_update_statement("REMOVE @stage/data.csv"));
+    }
+
+    #[test]
+    fn read_only_statements() {
+        assert!(is_read_only("/* explore */ select * from t"));
+        assert!(is_read_only("WITH x AS (SELECT 1) SELECT * FROM x"));
+        assert!(is_read_only("show tables"));
+        assert!(!is_read_only("DROP TABLE t"));
+        assert!(!is_read_only("-- harmless?\nDELETE FROM t"));
+        assert!(!is_read_only("CALL cleanup()"));
+    }
+
+    #[test]
+    fn split_statements_respects_quoting() {
+        let script = "
+            SELECT 'a;b' AS x;
+            -- comment; with semicolon
+            SELECT \"odd;name\" FROM t; /* ; */
+            CREATE PROCEDURE p() RETURNS INT LANGUAGE SQL AS $$ BEGIN RETURN 1; END $$;
+            (SELECT 1) UNION ALL (SELECT 2);
+            SELECT 'it\\'s; x', 'C:\\\\';
+            -- trailing comment only
+        ";
+        let statements = split_statements(script);
+        assert_eq!(statements.len(), 5);
+        assert_eq!(statements[0], "SELECT 'a;b' AS x");
+        assert!(statements[1].ends_with("SELECT \"odd;name\" FROM t"));
+        assert!(statements[2].contains("RETURN 1; END $$"));
+        assert_eq!(statements[3], "(SELECT 1) UNION ALL (SELECT 2)");
+        assert_eq!(statements[4], "SELECT 'it\\'s; x', 'C:\\\\'");
+    }
+
+    #[test]
+    fn statement_names() {
+        assert_eq!(
+            statement_name("-- name: daily totals\nSELECT 1").as_deref(),
+            Some("daily_totals")
+        );
+        assert_eq!(statement_name("SELECT 1 -- name: nope"), None);
+        assert_eq!(statement_name("-- just a comment\nSELECT 1"), None);
+    }
+
+    #[test]
+    fn parse_secondary_roles() {
+        assert_eq!("all".parse(), Ok(SecondaryRoles::All));
+        assert_eq!(" NONE ".parse(), Ok(SecondaryRoles::None));
+        let roles: SecondaryRoles = "analyst, \"Data Eng\"".parse().unwrap();
+        assert_eq!(roles.to_sql(), "USE SECONDARY ROLES analyst, \"Data Eng\"");
+        assert!("analyst; DROP TABLE t".parse::<SecondaryRoles>().is_err());
+        assert!("analyst,".parse::<SecondaryRoles>().is_err());
+        assert!("".parse::<SecondaryRoles>().is_err());
+    }
+
+    #[test]
+    fn quote_identifiers() {
+        assert_eq!(identifier("compute_wh"), "compute_wh");
+        assert_eq!(identifier("\"Reporting WH\""), "\"Reporting WH\"");
+        assert_eq!(identifier("wh; DROP TABLE t"), "\"wh; DROP TABLE t\"");
+        assert_eq!(identifier("a\"b"), "\"a\"\"b\"");
+    }
+}
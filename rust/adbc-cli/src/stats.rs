@@ -0,0 +1,351 @@
+//! Summary statistics over fetched results, similar to pandas' `describe()`.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use arrow_array::cast::AsArray;
+use arrow_array::types::{
+    Decimal128Type, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type,
+    UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+};
+use arrow_array::{Array, ArrayRef, RecordBatch, StringArray, UInt64Array};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+
+/// Distinct values are counted exactly up to this many, then reported as a
+/// lower bound to keep memory bounded.
+const MAX_DISTINCT: usize = 100_000;
+
+/// The non-null values of a numeric column. Integers are kept exact, as an
+/// `f64` can't hold every Int64 or UInt64 (e.g. IDs above 2^53).
+enum Values {
+    Integers(Vec<i128>),
+    Floats(Vec<f64>),
+}
+
+#[derive(Debug, Default)]
+struct NumericSummary {
+    min: f64,
+    max: f64,
+    /// The exact minimum and maximum of an integer column.
+    integer_range: Option<(i128, i128)>,
+    sum: f64,
+    count: usize,
+    distinct: HashSet<u64>,
+}
+
+impl NumericSummary {
+    fn add(&mut self, value: f64, key: u64) {
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.sum += value;
+        self.count += 1;
+        if self.distinct.len() < MAX_DISTINCT {
+            self.distinct.insert(key);
+        }
+    }
+
+    fn add_values(&mut self, values: Values) {
+        match values {
+            Values::Integers(values) => {
+                for value in values {
+                    let (min, max) = self.integer_range.get_or_insert((value, value));
+                    *min = (*min).min(value);
+                    *max = (*max).max(value);
+                    // Every value of one integer type has its own low 64 bits.
+                    self.add(value as f64, value as u64);
+                }
+            }
+            Values::Floats(values) => values
+                .into_iter()
+                .for_each(|value| self.add(value, value.to_bits())),
+        }
+    }
+
+    fn min(&self) -> String {
+        match self.integer_range {
+            Some((min, _)) => min.to_string(),
+            None => format_number(self.min),
+        }
+    }
+
+    fn max(&self) -> String {
+        match self.integer_range {
+            Some((_, max)) => max.to_string(),
+            None => format_number(self.max),
+        }
+    }
+
+    fn distinct(&self) -> String {
+        if self.distinct.len() >= MAX_DISTINCT {
+            format!("{}+", MAX_DISTINCT)
+        } else {
+            self.distinct.len().to_string()
+        }
+    }
+}
+
+/// Returns the non-null values of a numeric array, or `None` for non-numeric
+/// types.
+fn numeric_values(array: &dyn Array) -> Option<Values> {
+    macro_rules! values {
+        ($variant:ident, $t:ty, $as:ty) => {
+            Values::$variant(
+                array
+                    .as_primitive::<$t>()
+                    .iter()
+                    .flatten()
+                    .map(|v| v as $as)
+                    .collect(),
+            )
+        };
+    }
+
+    Some(match array.data_type() {
+        DataType::Int8 => values!(Integers, Int8Type, i128),
+        DataType::Int16 => values!(Integers, Int16Type, i128),
+        DataType::Int32 => values!(Integers, Int32Type, i128),
+        DataType::Int64 => values!(Integers, Int64Type, i128),
+        DataType::UInt8 => values!(Integers, UInt8Type, i128),
+        DataType::UInt16 => values!(Integers, UInt16Type, i128),
+        DataType::UInt32 => values!(Integers, UInt32Type, i128),
+        DataType::UInt64 => values!(Integers, UInt64Type, i128),
+        DataType::Float32 => values!(Floats, Float32Type, f64),
+        DataType::Float64 => values!(Floats, Float64Type, f64),
+        DataType::Decimal128(_, scale) => {
+            let divisor = 10f64.powi(i32::from(*scale));
+            Values::Floats(
+                array
+                    .as_primitive::<Decimal128Type>()
+                    .iter()
+                    .flatten()
+                    .map(|v| v as f64 / divisor)
+                    .collect(),
+            )
+        }
+        _ => return None,
+    })
+}
+
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        format!("{:.4}", value)
+    }
+}
+
+/// Summarize each column of `batches` as a batch with one row per column:
+/// count, nulls, and, for numeric columns, min, max, mean, and distinct.
+pub fn describe(schema: &SchemaRef, batches: &[RecordBatch]) -> RecordBatch {
+    let mut names = Vec::new();
+    let mut counts = Vec::new();
+    let mut nulls = Vec::new();
+    let mut mins = Vec::new();
+    let mut maxs = Vec::new();
+    let mut means = Vec::new();
+    let mut distincts = Vec::new();
+
+    for (idx, field) in schema.fields().iter().enumerate() {
+        let mut count = 0;
+        let mut null_count = 0;
+        let mut numeric: Option<NumericSummary> = None;
+
+        for batch in batches {
+            let column = batch.column(idx);
+            count += (column.len() - column.null_count()) as u64;
+            null_count += column.null_count() as u64;
+            if let Some(values) = numeric_values(column.as_ref()) {
+                numeric
+                    .get_or_insert_with(Default::default)
+                    .add_values(values);
+            }
+        }
+
+        names.push(field.name().clone());
+        counts.push(count);
+        nulls.push(null_count);
+        match numeric.filter(|summary| summary.count > 0) {
+            Some(summary) => {
+                mins.push(Some(summary.min()));
+                maxs.push(Some(summary.max()));
+                means.push(Some(format_number(summary.sum / summary.count as f64)));
+                distincts.push(Some(summary.distinct()));
+            }
+            None => {
+                mins.push(None);
+                maxs.push(None);
+                means.push(None);
+                distincts.push(None);
+            }
+        }
+    }
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("column", DataType::Utf8, false),
+        Field::new("count", DataType::UInt64, false),
+        Field::new("nulls", DataType::UInt64, false),
+        Field::new("min", DataType::Utf8, true),
+        Field::new("max", DataType::Utf8, true),
+        Field::new("mean", DataType::Utf8, true),
+        Field::new("distinct", DataType::Utf8, true),
+    ]));
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from(names)),
+        Arc::new(UInt64Array::from(counts)),
+        Arc::new(UInt64Array::from(nulls)),
+        Arc::new(StringArray::from(mins)),
+        Arc::new(StringArray::from(maxs)),
+        Arc::new(StringArray::from(means)),
+        Arc::new(StringArray::from(distincts)),
+    ];
+    RecordBatch::try_new(schema, columns).expect("summary columns match schema")
+}
+
+/// Count the nulls in each column of `batches` from their null buffers, as a
+/// batch with one row per column: non-null and null counts, and the
+/// percentage of rows that are null.
+pub fn null_counts(schema: &SchemaRef, batches: &[RecordBatch]) -> RecordBatch {
+    let mut names = Vec::new();
+    let mut non_nulls = Vec::new();
+    let mut nulls = Vec::new();
+    let mut pcts = Vec::new();
+
+    for (idx, field) in schema.fields().iter().enumerate() {
+        let (rows, null_count) = batches.iter().fold((0, 0), |(rows, null_count), batch| {
+            let column = batch.column(idx);
+            (
+                rows + column.len() as u64,
+                null_count + column.null_count() as u64,
+            )
+        });
+        names.push(field.name().clone());
+        non_nulls.push(rows - null_count);
+        nulls.push(null_count);
+        pcts.push(match rows {
+            0 => "-".to_string(),
+            _ => format!("{:.1}%", null_count as f64 * 100.0 / rows as f64),
+        });
+    }
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("column", DataType::Utf8, false),
+        Field::new("non_null", DataType::UInt64, false),
+        Field::new("null", DataType::UInt64, false),
+        Field::new("pct", DataType::Utf8, false),
+    ]));
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from(names)),
+        Arc::new(UInt64Array::from(non_nulls)),
+        Arc::new(UInt64Array::from(nulls)),
+        Arc::new(StringArray::from(pcts)),
+    ];
+    RecordBatch::try_new(schema, columns).expect("null count columns match schema")
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{Int64Array, StringArray};
+
+    use super::*;
+
+    #[test]
+    fn describe_numeric_and_string_columns() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("N", DataType::Int64, true),
+            Field::new("S", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![Some(1), None, Some(3), Some(3)])),
+                Arc::new(StringArray::from(vec![Some("a"), Some("b"), None, None])),
+            ],
+        )
+        .unwrap();
+
+        let summary = describe(&schema, &[batch]);
+        let column = |name: &str| summary.column_by_name(name).unwrap().clone();
+
+        let counts = column("count");
+        assert_eq!(counts.as_primitive::<UInt64Type>().values(), &[3, 2]);
+        let nulls = column("nulls");
+        assert_eq!(nulls.as_primitive::<UInt64Type>().values(), &[1, 2]);
+
+        let min = column("min");
+        let max = column("max");
+        let mean = column("mean");
+        let distinct = column("distinct");
+        assert_eq!(min.as_string::<i32>().value(0), "1");
+        assert_eq!(max.as_string::<i32>().value(0), "3");
+        assert_eq!(mean.as_string::<i32>().value(0), "2.3333");
+        assert_eq!(distinct.as_string::<i32>().value(0), "2");
+        assert!(min.is_null(1));
+        assert!(distinct.is_null(1));
+    }
+
+    #[test]
+    fn describe_wide_integers_exactly() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("I", DataType::Int64, false),
+            Field::new("U", DataType::UInt64, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![i64::MIN, i64::MAX, i64::MAX - 1])),
+                Arc::new(UInt64Array::from(vec![0, u64::MAX, u64::MAX - 1])),
+            ],
+        )
+        .unwrap();
+
+        let summary = describe(&schema, &[batch]);
+        let column = |name: &str| summary.column_by_name(name).unwrap().clone();
+        let (min, max, distinct) = (column("min"), column("max"), column("distinct"));
+        assert_eq!(min.as_string::<i32>().value(0), i64::MIN.to_string());
+        assert_eq!(max.as_string::<i32>().value(0), i64::MAX.to_string());
+        assert_eq!(min.as_string::<i32>().value(1), "0");
+        assert_eq!(max.as_string::<i32>().value(1), u64::MAX.to_string());
+        // i64::MAX and i64::MAX - 1 are the same f64, but distinct values.
+        assert_eq!(distinct.as_string::<i32>().value(0), "3");
+        assert_eq!(distinct.as_string::<i32>().value(1), "3");
+    }
+
+    #[test]
+    fn count_nulls() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("N", DataType::Int64, true),
+            Field::new("S", DataType::Utf8, true),
+        ]));
+        let batch = |n: Vec<Option<i64>>, s: Vec<Option<&str>>| {
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int64Array::from(n)),
+                    Arc::new(StringArray::from(s)),
+                ],
+            )
+            .unwrap()
+        };
+        let batches = [
+            batch(vec![Some(1), None], vec![None, None]),
+            batch(vec![Some(3)], vec![Some("a")]),
+        ];
+
+        let counts = null_counts(&schema, &batches);
+        let non_null = counts.column_by_name("non_null").unwrap();
+        assert_eq!(non_null.as_primitive::<UInt64Type>().values(), &[2, 1]);
+        let null = counts.column_by_name("null").unwrap();
+        assert_eq!(null.as_primitive::<UInt64Type>().values(), &[1, 2]);
+        let pct = counts.column_by_name("pct").unwrap().as_string::<i32>();
+        assert_eq!(pct.value(0), "33.3%");
+        assert_eq!(pct.value(1), "66.7%");
+
+        let empty = null_counts(&schema, &[]);
+        assert_eq!(empty.column(3).as_string::<i32>().value(0), "-");
+    }
+}
@@ -0,0 +1,80 @@
+//! Checking on, fetching the results of, and cancelling queries by ID.
+//!
+//! The Snowflake ADBC driver always waits for a query to finish, so queries
+//! can't be submitted asynchronously from here. These helpers instead look up
+//! queries started elsewhere (or by an earlier, interrupted run) in Snowflake's
+//! query history, and re-read the results of finished ones with `RESULT_SCAN`,
+//! which Snowflake keeps for 24 hours.
+
+use anyhow::{bail, Result};
+
+/// Check that `id` looks like a Snowflake query ID (a UUID), since it is
+/// interpolated into SQL.
+pub fn validate_query_id(id: &str) -> Result<()> {
+    let groups: Vec<&str> = id.split('-').collect();
+    let lengths = [8, 4, 4, 4, 12];
+    let valid = groups.len() == lengths.len()
+        && groups
+            .iter()
+            .zip(lengths)
+            .all(|(group, len)| group.len() == len && group.chars().all(|c| c.is_ascii_hexdigit()));
+    if !valid {
+        bail!("Invalid query ID: {}", id);
+    }
+    Ok(())
+}
+
+/// SQL returning the status of query `id` from the current user's recent
+/// query history.
+pub fn status_sql(id: &str) -> Result<String> {
+    validate_query_id(id)?;
+    Ok(format!(
+        "SELECT query_id, execution_status, start_time, end_time, total_elapsed_time, \
+         rows_produced, error_message \
+         FROM TABLE(INFORMATION_SCHEMA.QUERY_HISTORY(RESULT_LIMIT => 10000)) \
+         WHERE query_id = '{}'",
+        id
+    ))
+}
+
+/// SQL returning the ID of the previous query in the session.
+pub const LAST_QUERY_ID_SQL: &str = "SELECT LAST_QUERY_ID()";
+
+/// SQL returning the bytes scanned by query `id`, run in the same session.
+pub fn bytes_scanned_sql(id: &str) -> Result<String> {
+    validate_query_id(id)?;
+    Ok(format!(
+        "SELECT bytes_scanned \
+         FROM TABLE(INFORMATION_SCHEMA.QUERY_HISTORY_BY_SESSION(RESULT_LIMIT => 100)) \
+         WHERE query_id = '{}'",
+        id
+    ))
+}
+
+/// SQL re-reading the result of the finished query `id`.
+pub fn result_sql(id: &str) -> Result<String> {
+    validate_query_id(id)?;
+    Ok(format!("SELECT * FROM TABLE(RESULT_SCAN('{}'))", id))
+}
+
+/// SQL cancelling the running query `id`.
+pub fn cancel_sql(id: &str) -> Result<String> {
+    validate_query_id(id)?;
+    Ok(format!("SELECT SYSTEM$CANCEL_QUERY('{}')", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_ids() {
+        let id = "01b2c3d4-0000-1a2b-0000-0123456789ab";
+        assert!(validate_query_id(id).is_ok());
+        assert!(result_sql(id).unwrap().contains(id));
+        assert!(cancel_sql(id).unwrap().contains(id));
+        assert!(bytes_scanned_sql(id).unwrap().contains(id));
+        assert!(validate_query_id("01b2c3d4").is_err());
+        assert!(status_sql("01b2c3d4-0000-1a2b-0000-0123456789a'").is_err());
+    }
+}
@@ -0,0 +1,142 @@
+//! Checking SQL syntax locally with `sqlparser`'s Snowflake dialect, for
+//! `--parse-only`, so typos are caught without a round trip to Snowflake.
+//!
+//! The parser doesn't know all of Snowflake's syntax, even in queries and DML
+//! (e.g. `PUT`, `INSERT ALL`, stage references, or `CHANGES` clauses), so a
+//! statement it can't parse may well be valid and gets a warning. Only
+//! errors in tokenizing, such as an unterminated string or comment, which
+//! no dialect accepts, fail the check.
+
+use std::fmt;
+
+use sqlparser::dialect::SnowflakeDialect;
+use sqlparser::parser::{Parser, ParserError};
+
+use crate::sql;
+
+/// A statement the parser rejected.
+#[derive(Debug, PartialEq)]
+pub struct Problem {
+    /// The statement's position in the script, from 1.
+    pub statement: usize,
+    /// Line and column in the script, from 1, if the parser gave them.
+    pub location: Option<(u64, u64)>,
+    pub message: String,
+    /// Whether the statement couldn't be tokenized, so the problem is an
+    /// error rather than a warning.
+    pub is_error: bool,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = if self.is_error { "Error" } else { "Warning" };
+        write!(f, "{}: statement {}", kind, self.statement)?;
+        if let Some((line, column)) = self.location {
+            write!(f, ", line {}, column {}", line, column)?;
+        }
+        write!(f, ": {}", self.message)?;
+        if !self.is_error {
+            write!(f, " (the parser may not support this Snowflake syntax)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Split the parser's message into the message and the line and column it
+/// appends as ` at Line: L, Column: C`.
+fn split_location(message: &str) -> (&str, Option<(u64, u64)>) {
+    let Some((message, location)) = message.rsplit_once(" at Line: ") else {
+        return (message, None);
+    };
+    let location = location
+        .split_once(", Column: ")
+        .and_then(|(line, column)| Some((line.trim().parse().ok()?, column.trim().parse().ok()?)));
+    (message, location)
+}
+
+/// The line and column, from 1, of byte `offset` in `text`.
+fn position(text: &str, offset: usize) -> (u64, u64) {
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .count()
+        + 1;
+    (line as u64, column as u64)
+}
+
+/// Parse each statement of `script`, returning the problems found, with
+/// locations in the script rather than the statement.
+pub fn check(script: &str) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let mut offset = 0;
+    for (idx, statement) in sql::split_statements(script).iter().enumerate() {
+        // Statements are trimmed slices of the script, in order.
+        let start = script[offset..]
+            .find(statement.as_str())
+            .map_or(offset, |found| offset + found);
+        offset = start + statement.len();
+
+        let (message, is_error) = match Parser::parse_sql(&SnowflakeDialect {}, statement) {
+            Ok(_) => continue,
+            Err(ParserError::TokenizerError(message)) => (message, true),
+            Err(ParserError::ParserError(message)) => (message, false),
+            Err(e) => (e.to_string(), false),
+        };
+        let (message, location) = split_location(&message);
+        let (line, column) = position(script, start);
+        let location = location.map(|(l, c)| {
+            if l == 1 {
+                (line, column + c - 1)
+            } else {
+                (line + l - 1, c)
+            }
+        });
+        problems.push(Problem {
+            statement: idx + 1,
+            location,
+            message: message.to_string(),
+            is_error,
+        });
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_script_syntax() {
+        let script = "SELECT 1;\n\n  SELECT *\n  FROM t\n  WHERE a = = 1;\nSELECT * FORM t;\n\
+                      PUT file:///tmp/data.csv @stage;\nSELECT 'open";
+        let problems = check(script);
+        let summary: Vec<String> = problems.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            summary,
+            [
+                "Warning: statement 2, line 5, column 13: Expected: an expression, found: = \
+                 (the parser may not support this Snowflake syntax)",
+                "Warning: statement 3, line 6, column 10: Expected: end of statement, found: \
+                 FORM (the parser may not support this Snowflake syntax)",
+                "Warning: statement 4, line 7, column 1: Expected: an SQL statement, found: PUT \
+                 (the parser may not support this Snowflake syntax)",
+                "Error: statement 5, line 8, column 8: Unterminated string literal",
+            ]
+        );
+        assert!(
+            check("SELECT v:a.b::string FROM t QUALIFY ROW_NUMBER() OVER (ORDER BY a) = 1")
+                .is_empty()
+        );
+
+        // Valid Snowflake SQL the parser may not know is never an error.
+        let script = "SELECT $1, $2 FROM @stage/f.csv;\n\
+                      INSERT ALL INTO t1 INTO t2 SELECT 1;\n\
+                      SELECT * FROM t CHANGES(INFORMATION => DEFAULT) \
+                      AT(TIMESTAMP => '2024-01-01'::TIMESTAMP_LTZ)";
+        assert!(check(script).iter().all(|problem| !problem.is_error));
+    }
+}
@@ -0,0 +1,241 @@
+//! Writing each result both to the terminal and to a file, for `--tee`.
+//!
+//! The fetched batches fan out to two writers: the display, on the calling
+//! thread, and the file, on a thread of its own fed through a bounded
+//! channel, so neither needs the whole result in memory. With
+//! `--display-rows`, the display stops after that many rows while the file
+//! still gets every row.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+use arrow_array::{RecordBatch, RecordBatchReader};
+use arrow_schema::{ArrowError, SchemaRef};
+
+use crate::output::{self, OutputFormat, RenderOptions};
+
+/// Batches buffered between the display and the file writer.
+const CHANNEL_BATCHES: usize = 4;
+
+/// The file every result is also written to. Like `--output-file`, it is
+/// created once, so the results of a script follow each other in it, except
+/// that an Arrow or Parquet file holds one result, so each replaces the last.
+#[derive(Clone, Debug)]
+pub struct TeeOutput {
+    pub path: PathBuf,
+    pub format: OutputFormat,
+    /// Rows shown on the display, or `None` for all of them.
+    pub display_rows: Option<usize>,
+    file: Arc<Mutex<BufWriter<File>>>,
+    /// Whether a result has been written to the file.
+    written: Arc<AtomicBool>,
+}
+
+impl TeeOutput {
+    pub fn create(path: &Path, format: OutputFormat, display_rows: Option<usize>) -> Result<Self> {
+        Ok(TeeOutput {
+            path: path.to_path_buf(),
+            format,
+            display_rows,
+            file: Arc::new(Mutex::new(open(path)?)),
+            written: Arc::new(AtomicBool::new(false)),
+        })
+    }
+}
+
+fn open(path: &Path) -> Result<BufWriter<File>> {
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create --tee file {}", path.display()))?;
+    Ok(BufWriter::new(file))
+}
+
+/// Passes batches to the display, sending a copy of each to the file writer.
+/// Once the display has its rows, the rest of the result goes only to the
+/// file.
+struct Tee<'a, R> {
+    inner: R,
+    sender: Option<SyncSender<Result<RecordBatch, ArrowError>>>,
+    display_rows: Option<usize>,
+    shown: &'a AtomicUsize,
+    total: &'a AtomicUsize,
+}
+
+impl<R: RecordBatchReader> Iterator for Tee<'_, R> {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sender = self.sender.as_ref()?;
+        loop {
+            let batch = match self.inner.next() {
+                Some(Ok(batch)) => batch,
+                Some(Err(e)) => {
+                    // If the file writer has failed, its own error is reported.
+                    let _ = sender.send(Err(ArrowError::ExternalError(e.to_string().into())));
+                    self.sender = None;
+                    return Some(Err(e));
+                }
+                None => {
+                    self.sender = None;
+                    return None;
+                }
+            };
+            let _ = sender.send(Ok(batch.clone()));
+            self.total.fetch_add(batch.num_rows(), Ordering::Relaxed);
+
+            let shown = self.shown.load(Ordering::Relaxed);
+            let remaining = self.display_rows.map_or(usize::MAX, |max| max - shown);
+            if remaining == 0 {
+                continue;
+            }
+            let batch = batch.slice(0, batch.num_rows().min(remaining));
+            self.shown.fetch_add(batch.num_rows(), Ordering::Relaxed);
+            return Some(Ok(batch));
+        }
+    }
+}
+
+impl<R: RecordBatchReader> RecordBatchReader for Tee<'_, R> {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+}
+
+/// The batches the display passed on, for the file writer.
+struct Received {
+    receiver: Receiver<Result<RecordBatch, ArrowError>>,
+    schema: SchemaRef,
+}
+
+impl Iterator for Received {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.recv().ok()
+    }
+}
+
+impl RecordBatchReader for Received {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+/// Write `reader` to `out` in `format` and to the tee file in its format.
+pub fn write(
+    reader: impl RecordBatchReader + Send,
+    tee: &TeeOutput,
+    format: OutputFormat,
+    options: &RenderOptions,
+    out: &mut dyn Write,
+) -> Result<()> {
+    let (sender, receiver) = mpsc::sync_channel(CHANNEL_BATCHES);
+    let received = Received {
+        receiver,
+        schema: reader.schema(),
+    };
+    let (shown, total) = (AtomicUsize::new(0), AtomicUsize::new(0));
+    let reader = Tee {
+        inner: reader,
+        sender: Some(sender),
+        display_rows: tee.display_rows,
+        shown: &shown,
+        total: &total,
+    };
+
+    let (displayed, saved) = std::thread::scope(|scope| {
+        let file_writer = scope.spawn(|| -> Result<()> {
+            let mut file = tee
+                .file
+                .lock()
+                .map_err(|_| anyhow::anyhow!("--tee file writer panicked"))?;
+            if tee.format.is_binary() && tee.written.swap(true, Ordering::Relaxed) {
+                file.flush()?;
+                *file = open(&tee.path)?;
+            }
+            output::write_output(received, tee.format, options, &mut *file)?;
+            file.flush()?;
+            Ok(())
+        });
+        let displayed = output::write_output(reader, format, options, out);
+        let saved = file_writer
+            .join()
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("--tee file writer panicked")));
+        (displayed, saved)
+    });
+    displayed?;
+    saved.with_context(|| format!("Failed to write --tee file {}", tee.path.display()))?;
+
+    let (shown, total) = (shown.into_inner(), total.into_inner());
+    if shown < total {
+        eprintln!(
+            "Showed {} of {} rows; all {} are in {}",
+            shown,
+            total,
+            total,
+            tee.path.display()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{Int64Array, RecordBatchIterator};
+    use arrow_schema::{DataType, Field, Schema};
+
+    use super::*;
+
+    #[test]
+    fn write_to_display_and_file() {
+        let schema = Arc::new(Schema::new(vec![Field::new("ID", DataType::Int64, false)]));
+        let batches: Vec<_> = [vec![1, 2, 3], vec![4, 5]]
+            .into_iter()
+            .map(|ids| RecordBatch::try_new(schema.clone(), vec![Arc::new(Int64Array::from(ids))]))
+            .collect();
+        let path = std::env::temp_dir().join(format!("adbc-cli-tee-{}.csv", std::process::id()));
+        let tee = TeeOutput::create(&path, OutputFormat::Csv, Some(2)).unwrap();
+
+        let mut out = Vec::new();
+        write(
+            RecordBatchIterator::new(batches, schema.clone()),
+            &tee,
+            OutputFormat::Csv,
+            &RenderOptions::default(),
+            &mut out,
+        )
+        .unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap(), "ID\n1\n2\n");
+        assert_eq!(saved, "ID\n1\n2\n3\n4\n5\n");
+
+        // Each result replaces a binary file.
+        let path = path.with_extension("arrows");
+        let tee = TeeOutput::create(&path, OutputFormat::ArrowStream, None).unwrap();
+        for ids in [vec![1, 2, 3], vec![4, 5]] {
+            let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(Int64Array::from(ids))]);
+            write(
+                RecordBatchIterator::new([batch], schema.clone()),
+                &tee,
+                OutputFormat::Csv,
+                &RenderOptions::default(),
+                &mut Vec::new(),
+            )
+            .unwrap();
+        }
+        let file = File::open(&path).unwrap();
+        let batches: Vec<_> = arrow_ipc::reader::StreamReader::try_new(file, None)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].num_rows(), 2);
+    }
+}
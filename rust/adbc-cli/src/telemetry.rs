@@ -0,0 +1,70 @@
+//! OpenTelemetry export of the `tracing` spans emitted while connecting,
+//! executing, and fetching (enabled with the `otel` feature).
+
+use anyhow::{Context, Result};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+/// Flushes pending spans when dropped.
+pub struct Guard(SdkTracerProvider);
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        if let Err(e) = self.0.shutdown() {
+            eprintln!("Warning: failed to export traces: {}", e);
+        }
+    }
+}
+
+/// Returns the OTLP/HTTP traces URL for a collector `endpoint`, which may be
+/// given with or without the `/v1/traces` path.
+fn traces_url(endpoint: &str) -> String {
+    let endpoint = endpoint.trim_end_matches('/');
+    if endpoint.ends_with("/v1/traces") {
+        endpoint.to_string()
+    } else {
+        format!("{}/v1/traces", endpoint)
+    }
+}
+
+/// Install a global subscriber exporting spans to the OTLP/HTTP collector at
+/// `endpoint`. Spans are exported in the background and flushed when the
+/// returned guard is dropped.
+pub fn init(endpoint: &str) -> Result<Guard> {
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(traces_url(endpoint))
+        .build()
+        .context("Failed to create OTLP exporter")?;
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name("adbc-cli").build())
+        .build();
+
+    tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("adbc-cli")))
+        .try_init()
+        .context("Failed to install tracing subscriber")?;
+    Ok(Guard(provider))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn traces_urls() {
+        assert_eq!(
+            traces_url("http://localhost:4318"),
+            "http://localhost:4318/v1/traces"
+        );
+        assert_eq!(
+            traces_url("http://localhost:4318/v1/traces/"),
+            "http://localhost:4318/v1/traces"
+        );
+    }
+}
@@ -0,0 +1,210 @@
+//! Wall-clock timing of each phase of running a query, reported with
+//! `--profile-timing` to tell network, warehouse, and decode time apart, and
+//! the batch-size histogram shown with `--verbose`.
+
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use arrow_array::{RecordBatch, RecordBatchReader};
+use arrow_schema::{ArrowError, SchemaRef};
+
+/// Records consecutive phases, each lasting from the end of the previous one.
+pub struct Timer {
+    start: Instant,
+    last: Instant,
+    phases: Vec<(&'static str, Duration)>,
+}
+
+impl Timer {
+    pub fn start() -> Self {
+        let now = Instant::now();
+        Self {
+            start: now,
+            last: now,
+            phases: Vec::new(),
+        }
+    }
+
+    /// Record a phase ending now.
+    pub fn phase(&mut self, name: &'static str) {
+        self.phase_until(name, Instant::now());
+    }
+
+    fn phase_until(&mut self, name: &'static str, end: Instant) {
+        self.phases
+            .push((name, end.saturating_duration_since(self.last)));
+        self.last = end;
+    }
+
+    /// Record the time to the first batch and the time to drain the rest, as
+    /// far as the reader got.
+    pub fn fetch_phases(&mut self, times: &FetchStats) {
+        if let Some(first) = times.first_batch.get() {
+            self.phase_until("first batch", *first);
+        }
+        if let Some(drained) = times.drained.get() {
+            self.phase_until("drain", *drained);
+        }
+    }
+
+    pub fn write_report(&self, handle: &mut dyn Write) -> io::Result<()> {
+        writeln!(handle, "Timing:")?;
+        for (name, elapsed) in &self.phases {
+            writeln!(handle, "  {:<17}{:>10.2?}", name, elapsed)?;
+        }
+        writeln!(handle, "  {:<17}{:>10.2?}", "total", self.last - self.start)
+    }
+}
+
+/// When a [`Timed`] reader produced its first batch and ran out of batches,
+/// and the number of rows in each batch.
+#[derive(Debug, Default)]
+pub struct FetchStats {
+    first_batch: OnceLock<Instant>,
+    drained: OnceLock<Instant>,
+    batch_rows: Mutex<Vec<usize>>,
+}
+
+impl FetchStats {
+    pub fn batch_rows(&self) -> Vec<usize> {
+        self.batch_rows
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+/// Upper bounds (inclusive) of the histogram's rows-per-batch buckets; the
+/// last bucket is unbounded.
+const BUCKETS: &[usize] = &[0, 9, 99, 999, 9_999, 99_999];
+
+fn bucket_label(idx: usize) -> String {
+    match (
+        idx.checked_sub(1).map(|prev| BUCKETS[prev] + 1),
+        BUCKETS.get(idx),
+    ) {
+        (None, Some(max)) => max.to_string(),
+        (Some(min), Some(max)) => format!("{}-{}", min, max),
+        (Some(min), None) => format!("{}+", min),
+        (None, None) => unreachable!("BUCKETS is not empty"),
+    }
+}
+
+/// Write the number of batches and a histogram of their sizes, to show
+/// whether a driver returns many tiny batches or a few large ones.
+pub fn write_batch_histogram(batch_rows: &[usize], handle: &mut dyn Write) -> io::Result<()> {
+    writeln!(handle, "Batches: {}", batch_rows.len())?;
+    if batch_rows.is_empty() {
+        return Ok(());
+    }
+    let mut counts = vec![0usize; BUCKETS.len() + 1];
+    for rows in batch_rows {
+        counts[BUCKETS.partition_point(|max| max < rows)] += 1;
+    }
+    let widest = *counts.iter().max().unwrap_or(&1);
+    for (idx, count) in counts.iter().enumerate().filter(|(_, count)| **count > 0) {
+        let bar = "#".repeat((count * 40).div_ceil(widest));
+        writeln!(
+            handle,
+            "  {:>13} rows | {:>6} {}",
+            bucket_label(idx),
+            count,
+            bar
+        )?;
+    }
+    Ok(())
+}
+
+/// Wraps a reader, noting when batches start and stop arriving.
+pub struct Timed<R> {
+    inner: R,
+    times: Arc<FetchStats>,
+}
+
+impl<R: RecordBatchReader> Timed<R> {
+    pub fn new(inner: R, times: Arc<FetchStats>) -> Self {
+        Self { inner, times }
+    }
+}
+
+impl<R: RecordBatchReader> Iterator for Timed<R> {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let batch = self.inner.next();
+        let now = Instant::now();
+        match &batch {
+            Some(batch) => {
+                self.times.first_batch.get_or_init(|| now);
+                if let Ok(batch) = batch {
+                    self.times
+                        .batch_rows
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .push(batch.num_rows());
+                }
+            }
+            None => {
+                self.times.drained.get_or_init(|| now);
+            }
+        }
+        batch
+    }
+}
+
+impl<R: RecordBatchReader> RecordBatchReader for Timed<R> {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::RecordBatchIterator;
+    use arrow_schema::Schema;
+
+    use super::*;
+
+    #[test]
+    fn fetch_phases() {
+        let schema = Arc::new(Schema::empty());
+        let batches = vec![RecordBatch::new_empty(schema.clone()); 2];
+        let times = Arc::new(FetchStats::default());
+        let mut timer = Timer::start();
+        timer.phase("execute");
+
+        let reader = Timed::new(
+            RecordBatchIterator::new(batches.into_iter().map(Ok), schema),
+            times.clone(),
+        );
+        assert_eq!(reader.count(), 2);
+        timer.fetch_phases(&times);
+        timer.phase("output");
+
+        let names: Vec<&str> = timer.phases.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, ["execute", "first batch", "drain", "output"]);
+        let mut report = Vec::new();
+        timer.write_report(&mut report).unwrap();
+        let report = String::from_utf8(report).unwrap();
+        assert!(report.starts_with("Timing:\n  execute "));
+        assert!(report.contains("\n  total "));
+    }
+
+    #[test]
+    fn batch_histogram() {
+        assert_eq!(bucket_label(0), "0");
+        assert_eq!(bucket_label(2), "10-99");
+        assert_eq!(bucket_label(BUCKETS.len()), "100000+");
+
+        let mut out = Vec::new();
+        write_batch_histogram(&[1, 5, 100, 2048], &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Batches: 4\n\
+             \x20           1-9 rows |      2 ########################################\n\
+             \x20       100-999 rows |      1 ####################\n\
+             \x20     1000-9999 rows |      1 ####################\n"
+        );
+    }
+}
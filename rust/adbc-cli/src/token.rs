@@ -0,0 +1,203 @@
+//! OAuth tokens read from a file for a profile's `token_file`, as written by
+//! an external agent (e.g. a Kubernetes sidecar) that rotates the token.
+//!
+//! The file is read when the database is built and then re-read in the
+//! background; when its token changes, the new one is set on the database so
+//! later connections log in with it, and long interactive or benchmark
+//! sessions outlive the token they started with.
+//!
+//! The driver doesn't guard its configuration against a change while a
+//! connection is logging in, so updates wait for [`opening`] guards, and the
+//! refresher's messages wait in a queue for [`report`] rather than landing in
+//! the middle of a result.
+
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Condvar, Mutex, RwLock, RwLockReadGuard};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+
+/// How often [`Refresher`] re-reads the token file.
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Shared while connections open, and held alone while a token is set.
+static UPDATE: RwLock<()> = RwLock::new(());
+
+/// Messages from the refresher, waiting to be reported.
+static MESSAGES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Signalled when a message is queued.
+static QUEUED: Condvar = Condvar::new();
+
+/// Hold while opening a connection, so the token isn't changed under it.
+pub fn opening() -> RwLockReadGuard<'static, ()> {
+    UPDATE.read().unwrap_or_else(|e| e.into_inner())
+}
+
+fn queue(message: String) {
+    MESSAGES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(message);
+    QUEUED.notify_all();
+}
+
+fn take_messages() -> Vec<String> {
+    std::mem::take(&mut *MESSAGES.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// Write the refresher's messages since the last call to `handle`, between
+/// results.
+pub fn report(handle: &mut dyn Write) {
+    for message in take_messages() {
+        let _ = writeln!(handle, "{}", message);
+    }
+}
+
+/// Read the token in the file at `path`: one line, with surrounding
+/// whitespace trimmed.
+pub fn read(path: &Path) -> Result<String> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            bail!("Token file {} not found", path.display())
+        }
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+            bail!("Token file {} isn't valid UTF-8", path.display())
+        }
+        Err(e) => bail!("Failed to read token file {}: {}", path.display(), e),
+    };
+    let token = contents.trim();
+    if token.is_empty() {
+        bail!("Token file {} is empty", path.display());
+    }
+    // The token itself never appears in errors.
+    if !token.chars().all(|c| c.is_ascii_graphic()) {
+        bail!(
+            "Token file {} doesn't hold a token: expected a single line without spaces",
+            path.display()
+        );
+    }
+    Ok(token.to_string())
+}
+
+/// Re-reads a token file every interval until dropped, passing each new
+/// token to `apply`.
+pub struct Refresher {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Refresher {
+    /// Start watching `path`, whose token is `current`.
+    pub fn start(
+        path: PathBuf,
+        current: String,
+        interval: Duration,
+        mut apply: impl FnMut(&str) -> Result<()> + Send + 'static,
+    ) -> Self {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let thread = thread::spawn(move || {
+            let mut current = current;
+            let mut failing = false;
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                // Keep the current token while the file is being rewritten
+                // or is briefly missing.
+                let result = read(&path).and_then(|token| {
+                    if token != current {
+                        let _updating = UPDATE.write().unwrap_or_else(|e| e.into_inner());
+                        apply(&token)?;
+                        current = token;
+                        queue("Token file changed; new connections use the new token.".into());
+                    }
+                    Ok(())
+                });
+                match result {
+                    Ok(()) => failing = false,
+                    // Warn once per outage rather than on every read.
+                    Err(e) if !failing => {
+                        failing = true;
+                        queue(format!("Warning: token refresh failed: {:#}", e));
+                    }
+                    Err(_) => {}
+                }
+            }
+        });
+        Self {
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for Refresher {
+    fn drop(&mut self) {
+        // Closing the channel wakes and stops the thread.
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wait until `count` messages are queued, or fail after a generous
+    /// timeout.
+    fn wait_for_messages(count: usize) {
+        let messages = MESSAGES.lock().unwrap();
+        let (messages, _) = QUEUED
+            .wait_timeout_while(messages, Duration::from_secs(10), |messages| {
+                messages.len() < count
+            })
+            .unwrap();
+        assert!(messages.len() >= count, "{:?}", *messages);
+    }
+
+    #[test]
+    fn read_and_refresh_token() {
+        let path = std::env::temp_dir().join(format!("adbc-cli-token-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let message = |path: &Path| format!("{:#}", read(path).unwrap_err());
+        assert!(message(&path).ends_with("not found"));
+        std::fs::write(&path, "\n").unwrap();
+        assert!(message(&path).ends_with("is empty"));
+        std::fs::write(&path, "not a token").unwrap();
+        assert!(message(&path).contains("doesn't hold a token"));
+        std::fs::write(&path, "  first.jwt\n").unwrap();
+        assert_eq!(read(&path).unwrap(), "first.jwt");
+
+        let (applied, tokens) = mpsc::channel();
+        let refresher = Refresher::start(
+            path.clone(),
+            "first.jwt".to_string(),
+            Duration::from_millis(10),
+            move |token| {
+                let _ = applied.send(token.to_string());
+                Ok(())
+            },
+        );
+        // Replace the file at once, as a sidecar would, so it's never read
+        // half-written.
+        let staged = path.with_extension("new");
+        std::fs::write(&staged, "second.jwt\n").unwrap();
+        std::fs::rename(&staged, &path).unwrap();
+        let token = tokens.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert_eq!(token, "second.jwt");
+        wait_for_messages(1);
+        // A missing file keeps the last token.
+        std::fs::remove_file(&path).unwrap();
+        wait_for_messages(2);
+        drop(refresher);
+        assert!(tokens.try_recv().is_err());
+        let messages = take_messages();
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].starts_with("Token file changed"));
+        assert!(messages[1].ends_with("not found"));
+    }
+}
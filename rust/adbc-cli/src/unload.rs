@@ -0,0 +1,120 @@
+//! Server-side export of a query's result to a stage with `COPY INTO`, for
+//! extracts too large to fetch through the client.
+
+use anyhow::{bail, Result};
+use arrow_array::RecordBatch;
+
+use crate::output::format_value;
+
+/// File types Snowflake can unload to directly; any other `--file-format` is
+/// taken to be the name of a file format object.
+const FILE_TYPES: &[&str] = &["CSV", "JSON", "PARQUET"];
+
+/// The `FILE_FORMAT` clause for `file_format`, either a type such as
+/// `parquet` or the name of a file format object.
+fn file_format_clause(file_format: &str) -> String {
+    let upper = file_format.to_ascii_uppercase();
+    if FILE_TYPES.contains(&upper.as_str()) {
+        format!("FILE_FORMAT = (TYPE = {})", upper)
+    } else {
+        format!(
+            "FILE_FORMAT = (FORMAT_NAME = '{}')",
+            file_format.replace('\'', "''")
+        )
+    }
+}
+
+/// SQL unloading the result of `query` to `stage` (e.g. `@exports/daily/`),
+/// returning one row per file written with its name, size, and row count.
+pub fn unload_sql(query: &str, stage: &str, file_format: &str) -> Result<String> {
+    if !stage.starts_with('@') {
+        bail!("Stage location must start with '@', e.g. @my_stage/path/");
+    }
+    let query = query.trim().trim_end_matches(';');
+    if query.is_empty() {
+        bail!("Query is empty");
+    }
+    let mut sql = format!(
+        "COPY INTO {} FROM ({}) {} DETAILED_OUTPUT = TRUE",
+        stage,
+        query,
+        file_format_clause(file_format)
+    );
+    // Keep column names in CSV and Parquet output.
+    if !file_format.eq_ignore_ascii_case("json") {
+        sql.push_str(" HEADER = TRUE");
+    }
+    Ok(sql)
+}
+
+/// Sum the numeric `column` of a `COPY INTO` result.
+fn column_total(batches: &[RecordBatch], column: &str) -> u64 {
+    let mut total = 0;
+    for batch in batches {
+        let schema = batch.schema();
+        let Ok(idx) = schema.index_of(column) else {
+            continue;
+        };
+        for row in 0..batch.num_rows() {
+            let value = format_value(batch.column(idx).as_ref(), schema.field(idx), row);
+            total += value.parse::<u64>().unwrap_or(0);
+        }
+    }
+    total
+}
+
+/// The number of files, rows, and bytes written, from the detailed output of
+/// `COPY INTO`, which has a row per file.
+pub fn totals(batches: &[RecordBatch]) -> (usize, u64, u64) {
+    let files = batches.iter().map(RecordBatch::num_rows).sum();
+    (
+        files,
+        column_total(batches, "ROW_COUNT"),
+        column_total(batches, "FILE_SIZE"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::{Int64Array, StringArray};
+    use arrow_schema::{DataType, Field, Schema};
+
+    use super::*;
+
+    #[test]
+    fn unload_statements() {
+        assert_eq!(
+            unload_sql("SELECT * FROM t;", "@exports/t/", "parquet").unwrap(),
+            "COPY INTO @exports/t/ FROM (SELECT * FROM t) FILE_FORMAT = (TYPE = PARQUET) \
+             DETAILED_OUTPUT = TRUE HEADER = TRUE"
+        );
+        assert!(unload_sql("SELECT 1", "@s", "my_db.public.pipe_format")
+            .unwrap()
+            .contains("FORMAT_NAME = 'my_db.public.pipe_format'"));
+        assert!(!unload_sql("SELECT 1", "@s", "JSON")
+            .unwrap()
+            .contains("HEADER"));
+        assert!(unload_sql("SELECT 1", "s3://bucket/", "csv").is_err());
+    }
+
+    #[test]
+    fn unload_totals() {
+        let schema = Schema::new(vec![
+            Field::new("FILE_NAME", DataType::Utf8, false),
+            Field::new("FILE_SIZE", DataType::Int64, false),
+            Field::new("ROW_COUNT", DataType::Int64, false),
+        ]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(StringArray::from(vec!["data_0.parquet", "data_1.parquet"])),
+                Arc::new(Int64Array::from(vec![1024, 2048])),
+                Arc::new(Int64Array::from(vec![10, 20])),
+            ],
+        )
+        .unwrap();
+        assert_eq!(totals(&[batch]), (2, 30, 3072));
+    }
+}
@@ -0,0 +1,172 @@
+//! Time windows for recurring time-bounded queries: `--since 7d --until now`
+//! fills `{{since}}` and `{{until}}` in the query with timestamp literals, so
+//! shell scripts don't have to do date math.
+
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+
+/// A point in time given relative to now (e.g. `7d` ago) or as an ISO 8601
+/// timestamp.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TimeBound {
+    Ago(Duration),
+    At(DateTime<Utc>),
+}
+
+impl FromStr for TimeBound {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("now") {
+            return Ok(TimeBound::Ago(Duration::zero()));
+        }
+        if let Some((number, unit)) = s
+            .find(|c: char| !c.is_ascii_digit())
+            .filter(|&idx| idx > 0)
+            .map(|idx| s.split_at(idx))
+        {
+            if let Ok(number) = number.parse::<i64>() {
+                let ago = match unit {
+                    "s" => Duration::try_seconds(number),
+                    "m" => Duration::try_minutes(number),
+                    "h" => Duration::try_hours(number),
+                    "d" => Duration::try_days(number),
+                    "w" => Duration::try_weeks(number),
+                    _ => None,
+                };
+                if let Some(ago) = ago {
+                    return Ok(TimeBound::Ago(ago));
+                }
+            }
+        }
+        parse_timestamp(s).map(TimeBound::At).ok_or_else(|| {
+            format!(
+                "expected a relative time such as 7d, 24h, or 30m, 'now', or an ISO 8601 \
+                 timestamp, got '{}'",
+                s
+            )
+        })
+    }
+}
+
+/// Parse an RFC 3339 timestamp, or a date or date and time without an
+/// offset, which is taken to be UTC.
+fn parse_timestamp(s: &str) -> Option<DateTime<Utc>> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(s) {
+        return Some(timestamp.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"] {
+        if let Ok(timestamp) = NaiveDateTime::parse_from_str(s, format) {
+            return Some(timestamp.and_utc());
+        }
+    }
+    let date = NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?;
+    Some(date.and_hms_opt(0, 0, 0)?.and_utc())
+}
+
+impl TimeBound {
+    /// The point in time this bound names, relative to `now`. Fails if it
+    /// falls before the earliest representable time.
+    pub fn resolve(&self, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+        match self {
+            TimeBound::Ago(ago) => now.checked_sub_signed(*ago).with_context(|| {
+                format!(
+                    "{} days ago is before the earliest supported date",
+                    ago.num_days()
+                )
+            }),
+            TimeBound::At(timestamp) => Ok(*timestamp),
+        }
+    }
+}
+
+/// A SQL literal for `timestamp`, explicit about its offset so the session's
+/// time zone doesn't shift it.
+fn timestamp_literal(timestamp: DateTime<Utc>) -> String {
+    format!(
+        "'{}'::TIMESTAMP_TZ",
+        timestamp.format("%Y-%m-%d %H:%M:%S%.3f +00:00")
+    )
+}
+
+/// Replace `{{since}}` and `{{until}}` in `sql` with the bounds resolved
+/// against `now`. A placeholder without a bound is an error; a bound without
+/// a placeholder is reported.
+pub fn substitute(
+    sql: &str,
+    since: Option<&TimeBound>,
+    until: Option<&TimeBound>,
+    now: DateTime<Utc>,
+) -> Result<String> {
+    let mut sql = sql.to_string();
+    for (name, bound) in [("since", since), ("until", until)] {
+        let placeholder = format!("{{{{{}}}}}", name);
+        match (sql.contains(&placeholder), bound) {
+            (true, Some(bound)) => {
+                let timestamp = bound
+                    .resolve(now)
+                    .with_context(|| format!("Invalid --{}", name))?;
+                sql = sql.replace(&placeholder, &timestamp_literal(timestamp));
+            }
+            (true, None) => {
+                anyhow::bail!("The query uses {} but --{} wasn't given", placeholder, name)
+            }
+            (false, Some(_)) => eprintln!(
+                "Warning: --{} given but the query has no {}",
+                name, placeholder
+            ),
+            (false, None) => {}
+        }
+    }
+    Ok(sql)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_time_bounds() {
+        assert_eq!("7d".parse(), Ok(TimeBound::Ago(Duration::days(7))));
+        assert_eq!("24h".parse(), Ok(TimeBound::Ago(Duration::hours(24))));
+        assert_eq!("now".parse(), Ok(TimeBound::Ago(Duration::zero())));
+        let midnight = "2024-03-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!("2024-03-01".parse(), Ok(TimeBound::At(midnight)));
+        assert_eq!("2024-03-01 00:00:00".parse(), Ok(TimeBound::At(midnight)));
+        assert_eq!(
+            "2024-03-01T01:00:00+01:00".parse(),
+            Ok(TimeBound::At(midnight))
+        );
+        assert!("7y".parse::<TimeBound>().is_err());
+        assert!("d".parse::<TimeBound>().is_err());
+    }
+
+    #[test]
+    fn substitute_placeholders() {
+        let now = "2024-03-08T12:30:00Z".parse::<DateTime<Utc>>().unwrap();
+        let since = "7d".parse().unwrap();
+        let sql = substitute(
+            "SELECT * FROM events WHERE ts >= {{since}} AND ts < {{until}}",
+            Some(&since),
+            Some(&TimeBound::Ago(Duration::zero())),
+            now,
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            "SELECT * FROM events WHERE ts >= '2024-03-01 12:30:00.000 +00:00'::TIMESTAMP_TZ \
+             AND ts < '2024-03-08 12:30:00.000 +00:00'::TIMESTAMP_TZ"
+        );
+        assert!(substitute("SELECT {{until}}", Some(&since), None, now).is_err());
+
+        let long_ago = "100000000d".parse().unwrap();
+        let err = substitute("SELECT {{since}}", Some(&long_ago), None, now).unwrap_err();
+        assert_eq!(
+            format!("{:#}", err),
+            "Invalid --since: 100000000 days ago is before the earliest supported date"
+        );
+    }
+}